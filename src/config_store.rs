@@ -8,6 +8,8 @@ mod path;
 
 use copy::CopyPlan;
 
+use crate::workspace::DevContainerConfig;
+
 pub use commands::run_command;
 pub use path::config_name_from_path;
 
@@ -242,9 +244,7 @@ impl ConfigStore {
 
     /// Reads the "name" field from a devcontainer.json, if present.
     fn read_config_name(config_path: &Path) -> Option<String> {
-        let content = std::fs::read_to_string(config_path).ok()?;
-        let parsed: serde_json::Value = json5::from_str(&content).ok()?;
-        parsed["name"].as_str().map(String::from)
+        DevContainerConfig::from_file(config_path).ok()?.name
     }
 }
 
@@ -1,4 +1,5 @@
 use super::{ConfigEntry, ConfigStore};
+use crate::editor::Editor;
 use crate::opts::ConfigAction;
 use crate::ui;
 use color_eyre::eyre::Result;
@@ -33,7 +34,7 @@ fn print_entries(entries: &[ConfigEntry], long: bool) {
     }
 }
 
-pub fn run_command(action: ConfigAction, store: &ConfigStore, editor: &str) -> Result<()> {
+pub fn run_command(action: ConfigAction, store: &ConfigStore, editor: &Editor) -> Result<()> {
     match action {
         ConfigAction::Ui => {
             let entries = store.list();
@@ -50,7 +51,7 @@ pub fn run_command(action: ConfigAction, store: &ConfigStore, editor: &str) -> R
                 ui::pick_config(entries, ui::PickerOpts::default(), Some(&mut delete_cb))?;
             if let Some(config) = selected {
                 info!("Opening config '{}' for editing...", config.name);
-                std::process::Command::new(editor)
+                std::process::Command::new(&editor.command)
                     .arg(&config.root)
                     .output()?;
             }
@@ -1,4 +1,5 @@
 use bollard::Docker;
+use bollard::models::ContainerSummary;
 use bollard::query_parameters::{InspectContainerOptionsBuilder, ListContainersOptionsBuilder};
 use color_eyre::eyre::{Result, WrapErr};
 use log::debug;
@@ -8,6 +9,11 @@ mod commands;
 
 pub use commands::run_command;
 
+/// Label set by Docker Compose on all containers of a compose project.
+const COMPOSE_PROJECT_LABEL: &str = "com.docker.compose.project";
+/// Label set by Docker Compose with the service name of the container.
+const COMPOSE_SERVICE_LABEL: &str = "com.docker.compose.service";
+
 /// A running or stopped devcontainer discovered via Docker labels.
 #[derive(Debug, Clone)]
 pub struct Container {
@@ -23,6 +29,10 @@ pub struct Container {
     pub status: String,
     /// Image used by the container.
     pub image: String,
+    /// Docker Compose project from the `com.docker.compose.project` label.
+    pub compose_project: Option<String>,
+    /// Docker Compose service from the `com.docker.compose.service` label.
+    pub compose_service: Option<String>,
 }
 
 /// Detailed information about a single devcontainer.
@@ -46,6 +56,12 @@ pub struct ContainerInfo {
     pub ports: String,
     /// Bind mounts.
     pub mounts: Vec<String>,
+    /// Working directory inside the container.
+    pub working_dir: String,
+    /// Docker Compose project, if the container is part of one.
+    pub compose_project: Option<String>,
+    /// Docker Compose service, if the container is part of a compose project.
+    pub compose_service: Option<String>,
 }
 
 fn runtime() -> tokio::runtime::Runtime {
@@ -62,18 +78,61 @@ fn connect() -> Result<Docker> {
 }
 
 /// Lists devcontainers by querying Docker for containers with `devcontainer.local_folder` labels.
+///
+/// The other containers of Docker Compose based devcontainers are included as well
+/// and all containers of a compose project are grouped together.
 pub fn list(all: bool) -> Result<Vec<Container>> {
     runtime().block_on(list_async(all))
 }
 
+/// Lists all containers of a Docker Compose project.
+pub fn list_compose_project(project: &str, all: bool) -> Result<Vec<Container>> {
+    runtime().block_on(async {
+        let docker = connect()?;
+        list_by_label(&docker, &format!("{COMPOSE_PROJECT_LABEL}={project}"), all).await
+    })
+}
+
 async fn list_async(all: bool) -> Result<Vec<Container>> {
     let docker = connect()?;
 
+    let mut result = list_by_label(&docker, "devcontainer.local_folder", all).await?;
+
+    let mut projects: Vec<String> = result
+        .iter()
+        .filter_map(|c| c.compose_project.clone())
+        .collect();
+    projects.sort();
+    projects.dedup();
+
+    for project in projects {
+        // compose services besides the primary one don't carry the devcontainer labels
+        let primary = result
+            .iter()
+            .find(|c| c.compose_project.as_deref() == Some(project.as_str()))
+            .cloned();
+        let services =
+            list_by_label(&docker, &format!("{COMPOSE_PROJECT_LABEL}={project}"), all).await?;
+        for mut service in services {
+            if result.iter().any(|c| c.id == service.id) {
+                continue;
+            }
+            if let Some(primary) = &primary {
+                service.local_folder.clone_from(&primary.local_folder);
+                service.config_file.clone_from(&primary.config_file);
+            }
+            result.push(service);
+        }
+    }
+
+    let result = group_by_compose_project(result);
+    debug!("Found {} devcontainers", result.len());
+    Ok(result)
+}
+
+async fn list_by_label(docker: &Docker, label: &str, all: bool) -> Result<Vec<Container>> {
     let mut filters = HashMap::new();
-    filters.insert(
-        "label".to_string(),
-        vec!["devcontainer.local_folder".to_string()],
-    );
+    filters.insert("label".to_string(), vec![label.to_string()]);
 
     let options = ListContainersOptionsBuilder::default()
         .all(all)
@@ -85,31 +144,53 @@ async fn list_async(all: bool) -> Result<Vec<Container>> {
         .await
         .wrap_err("Failed to list containers")?;
 
-    let result: Vec<Container> = containers
+    Ok(containers.into_iter().filter_map(to_container).collect())
+}
+
+fn to_container(c: ContainerSummary) -> Option<Container> {
+    let id = c.id?;
+    let short_id = id[..12.min(id.len())].to_string();
+    let labels = c.labels.unwrap_or_default();
+    Some(Container {
+        short_id,
+        id,
+        local_folder: labels
+            .get("devcontainer.local_folder")
+            .cloned()
+            .unwrap_or_default(),
+        config_file: labels
+            .get("devcontainer.config_file")
+            .cloned()
+            .unwrap_or_default(),
+        status: c.status.unwrap_or_default(),
+        image: c.image.unwrap_or_default(),
+        compose_project: labels.get(COMPOSE_PROJECT_LABEL).cloned(),
+        compose_service: labels.get(COMPOSE_SERVICE_LABEL).cloned(),
+    })
+}
+
+/// Orders containers so that all containers of a compose project follow each other.
+///
+/// Groups keep the position of their first container, and are sorted by service name.
+fn group_by_compose_project(containers: Vec<Container>) -> Vec<Container> {
+    let mut groups: Vec<(Option<String>, Vec<Container>)> = Vec::new();
+    for container in containers {
+        match groups
+            .iter_mut()
+            .find(|(project, _)| project.is_some() && *project == container.compose_project)
+        {
+            Some((_, group)) => group.push(container),
+            None => groups.push((container.compose_project.clone(), vec![container])),
+        }
+    }
+
+    groups
         .into_iter()
-        .filter_map(|c| {
-            let id = c.id?;
-            let short_id = id[..12.min(id.len())].to_string();
-            let labels = c.labels.unwrap_or_default();
-            Some(Container {
-                short_id,
-                id,
-                local_folder: labels
-                    .get("devcontainer.local_folder")
-                    .cloned()
-                    .unwrap_or_default(),
-                config_file: labels
-                    .get("devcontainer.config_file")
-                    .cloned()
-                    .unwrap_or_default(),
-                status: c.status.unwrap_or_default(),
-                image: c.image.unwrap_or_default(),
-            })
+        .flat_map(|(_, mut group)| {
+            group.sort_by(|a, b| a.compose_service.cmp(&b.compose_service));
+            group
         })
-        .collect();
-
-    debug!("Found {} devcontainers", result.len());
-    Ok(result)
+        .collect()
 }
 
 /// Returns detailed information about a specific devcontainer.
@@ -160,6 +241,9 @@ async fn info_async(id: &str) -> Result<ContainerInfo> {
         created: detail.created.unwrap_or_default(),
         ports,
         mounts,
+        working_dir: config.working_dir.unwrap_or_default(),
+        compose_project: labels.get(COMPOSE_PROJECT_LABEL).cloned(),
+        compose_service: labels.get(COMPOSE_SERVICE_LABEL).cloned(),
         local_folder: labels
             .get("devcontainer.local_folder")
             .cloned()
@@ -204,3 +288,39 @@ fn format_ports(ports: &HashMap<String, Option<Vec<bollard::models::PortBinding>
         formatted.join(", ")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn container(id: &str, project: Option<&str>, service: Option<&str>) -> Container {
+        Container {
+            id: id.to_string(),
+            short_id: id.to_string(),
+            local_folder: String::new(),
+            config_file: String::new(),
+            status: String::new(),
+            image: String::new(),
+            compose_project: project.map(ToOwned::to_owned),
+            compose_service: service.map(ToOwned::to_owned),
+        }
+    }
+
+    #[test]
+    fn test_group_by_compose_project() {
+        let containers = vec![
+            container("a", Some("shop"), Some("web")),
+            container("b", None, None),
+            container("c", Some("blog"), Some("app")),
+            container("d", None, None),
+            container("e", Some("shop"), Some("db")),
+        ];
+
+        let ids: Vec<String> = group_by_compose_project(containers)
+            .into_iter()
+            .map(|c| c.id)
+            .collect();
+
+        assert_eq!(ids, vec!["e", "a", "b", "c", "d"]);
+    }
+}
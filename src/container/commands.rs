@@ -1,4 +1,5 @@
-use super::{info, list, stop};
+use super::{Container, info, list, list_compose_project, stop};
+use crate::editor::{Editor, LaunchMode};
 use crate::opts::ContainerAction;
 use crate::ui;
 use crate::workspace::{self, DevContainer, Workspace};
@@ -6,7 +7,7 @@ use color_eyre::eyre::{Result, bail};
 use log::info;
 use std::path::{Path, PathBuf};
 
-fn run_ui(editor: &str) -> Result<()> {
+fn run_ui(editor: &Editor, dry_run: bool) -> Result<()> {
     let containers = list(false)?;
     if containers.is_empty() {
         println!("no running devcontainers");
@@ -18,11 +19,19 @@ fn run_ui(editor: &str) -> Result<()> {
             log::warn!("Failed to stop container {}: {error}", item.0.short_id);
         }
     };
-    let selected = ui::pick_container(containers, ui::PickerOpts::default(), Some(&mut stop_cb))?;
-    let Some(container) = selected else {
+    let opts = ui::PickerOpts {
+        allow_attach: true,
+        ..ui::PickerOpts::default()
+    };
+    let selected = ui::pick_container(containers, opts, Some(&mut stop_cb))?;
+    let Some((container, action)) = selected else {
         return Ok(());
     };
 
+    if action == ui::PickerAction::Attach {
+        return attach(&container.id, editor, dry_run);
+    }
+
     info!("Reopening container {} ...", container.short_id);
     let container_info = info(&container.id)?;
     let local_folder = workspace::resolve_local_path(&container_info.local_folder);
@@ -35,13 +44,39 @@ fn run_ui(editor: &str) -> Result<()> {
     let config_file = workspace::resolve_local_path(&container_info.config_file);
     let config_path = PathBuf::from(config_file);
     if config_path.exists() {
-        let dev_container = DevContainer::from_config(&config_path, &workspace.name)?;
-        workspace.open(vec![], false, &dev_container, editor, None)
+        let dev_container = DevContainer::from_config(&config_path, workspace.folder())?;
+        workspace.open(
+            vec![],
+            dry_run,
+            LaunchMode::Check,
+            &dev_container,
+            editor,
+            None,
+        )
     } else {
-        workspace.open_classic(vec![], false, editor)
+        workspace.open_classic(vec![], dry_run, LaunchMode::Check, editor)
     }
 }
 
+fn attach(id: &str, editor: &Editor, dry_run: bool) -> Result<()> {
+    let container_info = info(id)?;
+    info!("Attaching to container {} ...", container_info.name);
+
+    let folder = if container_info.working_dir.is_empty() {
+        "/"
+    } else {
+        container_info.working_dir.as_str()
+    };
+    workspace::open_attached(
+        &container_info.name,
+        folder,
+        vec![],
+        dry_run,
+        LaunchMode::Check,
+        editor,
+    )
+}
+
 fn print_containers(all: bool) -> Result<()> {
     let containers = list(all)?;
     if containers.is_empty() {
@@ -60,19 +95,38 @@ fn print_containers(all: bool) -> Result<()> {
         .map(|container| container.image.len())
         .max()
         .unwrap_or(5);
+    let compose_width = containers
+        .iter()
+        .map(|container| compose_label(container).len())
+        .max()
+        .unwrap_or(7)
+        .max(7);
     println!(
-        "{:<id_width$}  {:<status_width$}  {:<image_width$}  PROJECT PATH",
-        "CONTAINER ID", "STATUS", "IMAGE"
+        "{:<id_width$}  {:<status_width$}  {:<image_width$}  {:<compose_width$}  PROJECT PATH",
+        "CONTAINER ID", "STATUS", "IMAGE", "COMPOSE"
     );
     for container in containers {
         println!(
-            "{:<id_width$}  {:<status_width$}  {:<image_width$}  {}",
-            container.short_id, container.status, container.image, container.local_folder
+            "{:<id_width$}  {:<status_width$}  {:<image_width$}  {:<compose_width$}  {}",
+            container.short_id,
+            container.status,
+            container.image,
+            compose_label(&container),
+            container.local_folder
         );
     }
     Ok(())
 }
 
+/// Formats the compose project and service of a container as `project/service`.
+fn compose_label(container: &Container) -> String {
+    match (&container.compose_project, &container.compose_service) {
+        (Some(project), Some(service)) => format!("{project}/{service}"),
+        (Some(project), None) => project.clone(),
+        _ => String::new(),
+    }
+}
+
 fn print_container_info(id: &str) -> Result<()> {
     let container = info(id)?;
     let created = chrono::DateTime::parse_from_rfc3339(&container.created)
@@ -90,6 +144,26 @@ fn print_container_info(id: &str) -> Result<()> {
     println!("Project:      {}", container.local_folder);
     println!("Config:       {}", container.config_file);
     println!("Ports:        {}", container.ports);
+    if let Some(project) = &container.compose_project {
+        println!(
+            "Compose:      {project} (service: {})",
+            container.compose_service.as_deref().unwrap_or("unknown")
+        );
+        let services = list_compose_project(project, true)?;
+        for (index, service) in services.iter().enumerate() {
+            let line = format!(
+                "{} {} ({})",
+                service.compose_service.as_deref().unwrap_or("unknown"),
+                service.short_id,
+                service.status
+            );
+            if index == 0 {
+                println!("Services:     {line}");
+            } else {
+                println!("              {line}");
+            }
+        }
+    }
     if container.mounts.is_empty() {
         println!("Mounts:       none");
     } else {
@@ -104,11 +178,12 @@ fn print_container_info(id: &str) -> Result<()> {
     Ok(())
 }
 
-pub fn run_command(action: ContainerAction, editor: &str) -> Result<()> {
+pub fn run_command(action: ContainerAction, editor: &Editor, dry_run: bool) -> Result<()> {
     match action {
-        ContainerAction::Ui => run_ui(editor)?,
+        ContainerAction::Ui => run_ui(editor, dry_run)?,
         ContainerAction::List { all } => print_containers(all)?,
         ContainerAction::Info { id } => print_container_info(&id)?,
+        ContainerAction::Attach { id } => attach(&id, editor, dry_run)?,
         ContainerAction::Stop { id } => {
             stop(&id)?;
             info!("Stopped container {id}");
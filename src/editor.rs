@@ -0,0 +1,329 @@
+use log::{debug, trace, warn};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+mod commands;
+
+pub use commands::print_editors;
+
+/// Describes an editor vscli can launch.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Editor {
+    /// The unique id of the editor (e.g. "code"), which can be passed via `--command`.
+    pub id: String,
+    /// The name displayed in messages (e.g. "Visual Studio Code").
+    pub name: String,
+    /// The names of the executables, in order of preference.
+    pub binaries: Vec<String>,
+    /// Whether the editor can open `vscode-remote://dev-container+…` URIs.
+    #[serde(default = "default_true")]
+    pub dev_containers: bool,
+    /// The flag used to open a folder URI.
+    #[serde(default = "default_folder_uri_flag")]
+    pub folder_uri_flag: String,
+    /// The flag used to open a file URI.
+    #[serde(default = "default_file_uri_flag")]
+    pub file_uri_flag: String,
+    /// The command that is executed to launch the editor.
+    #[serde(skip)]
+    pub command: String,
+}
+
+/// How vscli waits for the launched editor.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LaunchMode {
+    /// Wait until the editor command returns and report a failure.
+    #[default]
+    Check,
+    /// Start the editor in the background without waiting for it.
+    Detach,
+    /// Pass `--wait` to the editor and wait until the opened window is closed.
+    Wait,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_folder_uri_flag() -> String {
+    "--folder-uri".to_string()
+}
+
+fn default_file_uri_flag() -> String {
+    "--file-uri".to_string()
+}
+
+impl Editor {
+    fn builtin(id: &str, name: &str, binaries: &[&str], dev_containers: bool) -> Self {
+        Self {
+            id: id.to_string(),
+            name: name.to_string(),
+            binaries: binaries.iter().map(ToString::to_string).collect(),
+            dev_containers,
+            folder_uri_flag: default_folder_uri_flag(),
+            file_uri_flag: default_file_uri_flag(),
+            command: String::new(),
+        }
+    }
+
+    /// Creates an editor for a command that is not part of the registry.
+    fn unknown(command: &str) -> Self {
+        Self {
+            id: command.to_string(),
+            name: format!("'{command}'"),
+            binaries: vec![command.to_string()],
+            dev_containers: true,
+            folder_uri_flag: default_folder_uri_flag(),
+            file_uri_flag: default_file_uri_flag(),
+            command: command.to_string(),
+        }
+    }
+
+    /// Finds the first installed binary of the editor and its location.
+    pub fn locate(&self) -> Option<(String, PathBuf)> {
+        self.binaries
+            .iter()
+            .find_map(|binary| find_in_path(binary).map(|path| (binary.clone(), path)))
+    }
+
+    /// Checks whether the given command refers to this editor.
+    fn matches(&self, command: &str) -> bool {
+        self.id.eq_ignore_ascii_case(command)
+            || self
+                .binaries
+                .iter()
+                .any(|binary| binary.eq_ignore_ascii_case(command))
+    }
+}
+
+/// The list of known editors.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct EditorRegistry {
+    editors: Vec<Editor>,
+    /// Editor ids (or binaries) that are preferred when detecting the default editor.
+    preference: Vec<String>,
+}
+
+impl Default for EditorRegistry {
+    fn default() -> Self {
+        Self {
+            preference: Vec::new(),
+            editors: vec![
+                Editor::builtin("code", "Visual Studio Code", &["code"], true),
+                Editor::builtin(
+                    "code-insiders",
+                    "Visual Studio Code Insiders",
+                    &["code-insiders"],
+                    true,
+                ),
+                Editor::builtin("cursor", "Cursor", &["cursor"], true),
+                Editor::builtin("windsurf", "Windsurf", &["windsurf"], true),
+                Editor::builtin("codium", "VSCodium", &["codium", "vscodium"], false),
+                Editor::builtin("positron", "Positron", &["positron"], false),
+            ],
+        }
+    }
+}
+
+impl EditorRegistry {
+    /// Adds custom editors (the `[[editors]]` of the user settings).
+    ///
+    /// Custom editors with the id of an editor that is already known are ignored.
+    pub fn add_custom(&mut self, custom: Vec<Editor>) {
+        for editor in custom {
+            if self.editors.iter().any(|known| known.id == editor.id) {
+                warn!(
+                    "Ignoring the custom editor `{}`, an editor with this id is already known",
+                    editor.id
+                );
+            } else {
+                self.editors.push(editor);
+            }
+        }
+    }
+
+    /// Sets the order in which editors are preferred when detecting the default editor.
+    ///
+    /// Editors that are not mentioned follow in registry order.
+    pub fn set_preference(&mut self, preference: Vec<String>) {
+        self.preference = preference;
+    }
+
+    /// Returns all known editors, in order of preference.
+    pub fn editors(&self) -> Vec<&Editor> {
+        let mut editors: Vec<&Editor> = self.editors.iter().collect();
+        // the sort is stable, so unmentioned editors keep the registry order
+        editors.sort_by_key(|editor| {
+            self.preference
+                .iter()
+                .position(|preferred| editor.matches(preferred))
+                .unwrap_or(usize::MAX)
+        });
+        editors
+    }
+
+    /// Returns the first installed editor, in order of preference.
+    pub fn detect(&self) -> Option<Editor> {
+        self.editors().into_iter().find_map(|editor| {
+            let (binary, path) = editor.locate()?;
+            debug!("Detected {} at {}", editor.name, path.display());
+            let mut editor = editor.clone();
+            editor.command = binary;
+            Some(editor)
+        })
+    }
+
+    /// Returns the editor used when none was specified: the first installed one or `code`.
+    pub fn default_editor(&self) -> Editor {
+        self.detect().unwrap_or_else(|| self.resolve("code"))
+    }
+
+    /// Returns the known editor with the given id, which uses the first installed binary.
+    pub fn resolve_id(&self, id: &str) -> Option<Editor> {
+        let editor = self
+            .editors
+            .iter()
+            .find(|editor| editor.id.eq_ignore_ascii_case(id))?;
+        Some(self.resolve(&editor.id))
+    }
+
+    /// Resolves an editor command (an editor id or binary name) to an editor.
+    ///
+    /// Unknown commands are treated as editors which support dev containers.
+    pub fn resolve(&self, command: &str) -> Editor {
+        let Some(editor) = self.editors.iter().find(|editor| editor.matches(command)) else {
+            trace!("Unknown editor command: {command}");
+            return Editor::unknown(command);
+        };
+
+        let mut editor = editor.clone();
+        // prefer the binary the user asked for, then the first installed one
+        editor.command = if editor.binaries.iter().any(|binary| binary == command) {
+            command.to_string()
+        } else {
+            editor
+                .locate()
+                .map(|(binary, _)| binary)
+                .or_else(|| editor.binaries.first().cloned())
+                .unwrap_or_else(|| command.to_string())
+        };
+        trace!("Resolved editor command `{command}` to {editor:?}");
+        editor
+    }
+}
+
+/// Searches the `PATH` for an executable with the given name.
+///
+/// Commands with a path separator (like `./code` or `bin/cursor`) are resolved against the current directory instead.
+pub fn find_in_path(binary: &str) -> Option<PathBuf> {
+    let extensions: Vec<String> = if cfg!(windows) {
+        std::env::var("PATHEXT")
+            .unwrap_or_else(|_| ".EXE;.CMD;.BAT".to_string())
+            .split(';')
+            .map(str::to_lowercase)
+            .chain(std::iter::once(String::new()))
+            .collect()
+    } else {
+        vec![String::new()]
+    };
+    let find_in = |dir: &Path| {
+        extensions
+            .iter()
+            .map(|extension| dir.join(format!("{binary}{extension}")))
+            .find(|candidate| candidate.is_file())
+    };
+
+    if binary.contains(std::path::is_separator) {
+        return find_in(&std::env::current_dir().ok()?);
+    }
+    let path = std::env::var_os("PATH")?;
+    std::env::split_paths(&path).find_map(|dir| find_in(&dir))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_editor() {
+        let registry = EditorRegistry::default();
+
+        let editor = registry.resolve("vscodium");
+        assert_eq!(editor.id, "codium");
+        assert_eq!(editor.name, "VSCodium");
+        assert_eq!(editor.command, "vscodium");
+        assert!(!editor.dev_containers);
+
+        let editor = registry.resolve("Cursor");
+        assert_eq!(editor.name, "Cursor");
+        assert_eq!(editor.command, "cursor");
+
+        let editor = registry.resolve("my-fork");
+        assert_eq!(editor.name, "'my-fork'");
+        assert_eq!(editor.command, "my-fork");
+        assert!(editor.dev_containers);
+    }
+
+    #[test]
+    fn test_find_relative_command() {
+        let cwd = std::env::current_dir().unwrap();
+        assert_eq!(find_in_path("src/main.rs"), Some(cwd.join("src/main.rs")));
+        assert_eq!(find_in_path("./Cargo.toml"), Some(cwd.join("./Cargo.toml")));
+        assert_eq!(find_in_path("./missing"), None);
+        // commands without a separator are only searched in PATH
+        assert_eq!(find_in_path("Cargo.toml"), None);
+    }
+
+    #[test]
+    fn test_editor_preference() {
+        let mut registry = EditorRegistry::default();
+        registry.set_preference(vec!["vscodium".to_string(), "cursor".to_string()]);
+
+        let ids: Vec<&str> = registry
+            .editors()
+            .iter()
+            .map(|editor| editor.id.as_str())
+            .collect();
+        assert_eq!(
+            ids,
+            [
+                "codium",
+                "cursor",
+                "code",
+                "code-insiders",
+                "windsurf",
+                "positron"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_custom_editors() {
+        let positron: Editor =
+            toml::from_str("id = \"positron\"\nname = \"Positron\"\nbinaries = [\"positron\"]")
+                .unwrap();
+        let fork: Editor = toml::from_str(
+            r#"
+            id = "fork"
+            name = "Fork"
+            binaries = ["fork-bin"]
+            dev-containers = false
+            folder-uri-flag = "--folder"
+            "#,
+        )
+        .unwrap();
+        let custom = vec![positron, fork.clone(), fork];
+        let mut registry = EditorRegistry::default();
+        registry.add_custom(custom);
+
+        // known editors are not replaced
+        assert!(!registry.resolve("positron").dev_containers);
+        assert_eq!(registry.editors().len(), 7);
+        let fork = registry.resolve("fork");
+        assert_eq!(fork.command, "fork-bin");
+        assert_eq!(fork.folder_uri_flag, "--folder");
+        assert_eq!(fork.file_uri_flag, "--file-uri");
+        assert!(!fork.dev_containers);
+    }
+}
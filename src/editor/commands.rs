@@ -0,0 +1,92 @@
+use super::{Editor, EditorRegistry};
+use log::debug;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// An editor of the registry, together with its installation (if any).
+struct Listing<'a> {
+    editor: &'a Editor,
+    path: Option<PathBuf>,
+    version: Option<String>,
+}
+
+/// Prints the known editors in order of preference, marking the default one with `*`.
+pub fn print_editors(registry: &EditorRegistry, default: &Editor, all: bool) {
+    let listings: Vec<Listing> = registry
+        .editors()
+        .into_iter()
+        .map(|editor| {
+            let path = editor.locate().map(|(_, path)| path);
+            let version = path.as_deref().and_then(version);
+            Listing {
+                editor,
+                path,
+                version,
+            }
+        })
+        .filter(|listing| all || listing.path.is_some())
+        .collect();
+
+    if listings.is_empty() {
+        println!("no editors found in PATH");
+        return;
+    }
+
+    let id_width = listings
+        .iter()
+        .map(|listing| listing.editor.id.len())
+        .max()
+        .unwrap_or(2)
+        + 2;
+    let name_width = listings
+        .iter()
+        .map(|listing| listing.editor.name.len())
+        .max()
+        .unwrap_or(4);
+    let version_width = listings
+        .iter()
+        .map(|listing| listing.version.as_deref().unwrap_or("-").len())
+        .max()
+        .unwrap_or(7)
+        .max(7);
+    println!(
+        "{:<id_width$}  {:<name_width$}  {:<version_width$}  DEV CONTAINERS  PATH",
+        "  ID", "NAME", "VERSION"
+    );
+    for listing in listings {
+        let marker = if listing.editor.id == default.id {
+            "*"
+        } else {
+            " "
+        };
+        println!(
+            "{:<id_width$}  {:<name_width$}  {:<version_width$}  {:<14}  {}",
+            format!("{marker} {}", listing.editor.id),
+            listing.editor.name,
+            listing.version.as_deref().unwrap_or("-"),
+            if listing.editor.dev_containers {
+                "yes"
+            } else {
+                "no"
+            },
+            listing.path.as_deref().map_or_else(
+                || "(not found)".to_string(),
+                |path| path.display().to_string()
+            ),
+        );
+    }
+}
+
+/// Reads the version of an editor from the first line of `--version`.
+fn version(path: &Path) -> Option<String> {
+    let output = Command::new(path).arg("--version").output().ok()?;
+    if !output.status.success() {
+        debug!("`{} --version` failed: {output:?}", path.display());
+        return None;
+    }
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .map(ToString::to_string)
+}
@@ -1,20 +1,37 @@
 use chrono::{DateTime, Utc};
-use color_eyre::eyre::{Context, Result, eyre};
+use color_eyre::eyre::{Context, Result, bail, eyre};
 use log::{debug, warn};
 use serde::{Deserialize, Serialize};
 use std::{
     cmp::Ordering,
     collections::HashMap,
     fs::{self, File},
-    path::PathBuf,
+    io::{ErrorKind, Write},
+    path::{Path, PathBuf},
     sync::atomic::AtomicUsize,
 };
 
 use crate::launch::Behavior;
+use crate::workspace::WorkspaceKind;
+
+mod commands;
+mod merge;
+mod relocate;
+mod schema;
+
+pub use commands::run_command;
+pub use relocate::{find_config_relocations, find_relocations, relocated};
 
 /// The maximum number of entries to keep in the history
 // This is an arbitrary number, but it should be enough to keep the history manageable
-const MAX_HISTORY_ENTRIES: usize = 35;
+pub const MAX_HISTORY_ENTRIES: usize = 35;
+
+/// The number of recent opens of an entry that are kept for its frecency
+const MAX_RECENT_OPENS: usize = 10;
+
+/// The weight of an open, by its age in days (older opens weigh the last value)
+const FRECENCY_WEIGHTS: [(i64, u64); 4] = [(4, 100), (14, 70), (31, 50), (90, 30)];
+const FRECENCY_MIN_WEIGHT: u64 = 10;
 
 /// An entry in the history
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -24,16 +41,29 @@ pub struct Entry {
     /// The name of the dev container, if it exists
     pub dev_container_name: Option<String>,
     /// The name of the external config used, if any
-    #[serde(default)]
     pub config_name: Option<String>,
     /// The path to the vscode workspace
     pub workspace_path: PathBuf,
+    /// Whether the workspace path is a folder or a `.code-workspace` file
+    pub workspace_kind: WorkspaceKind,
+    /// The ssh host the workspace lives on, if it is a remote workspace
+    pub remote_host: Option<String>,
     /// The path to the dev container config, if it exists
     pub config_path: Option<PathBuf>,
+    /// The folder opened inside the workspace, relative to it
+    pub subfolder: Option<PathBuf>,
+    /// The folder that was opened inside the dev container
+    pub container_folder: Option<String>,
     /// The launch behavior
     pub behavior: Behavior,
     /// The time this entry was last opened
     pub last_opened: DateTime<Utc>, // not used in PartialEq, Eq, Hash
+    /// How often this entry was opened
+    pub open_count: u32,
+    /// The times of the most recent opens, oldest first
+    pub recent_opens: Vec<DateTime<Utc>>,
+    /// Whether the entry is listed first and never removed by truncation
+    pub pinned: bool,
 }
 
 // Custom comparison which ignores `last_opened` (and `name`)
@@ -41,13 +71,84 @@ pub struct Entry {
 impl PartialEq for Entry {
     fn eq(&self, other: &Self) -> bool {
         self.workspace_path == other.workspace_path
+            && self.remote_host == other.remote_host
             && self.config_path == other.config_path
+            && self.subfolder == other.subfolder
             && self.behavior == other.behavior
     }
 }
 
 impl Eq for Entry {}
 
+impl Entry {
+    /// Returns the workspace location for display, prefixed with the ssh host for remote workspaces
+    pub fn location(&self) -> String {
+        match &self.remote_host {
+            Some(host) => format!("ssh://{host}{}", self.workspace_path.display()),
+            None => self.workspace_path.to_string_lossy().into_owned(),
+        }
+    }
+
+    /// Whether the workspace or its dev container config no longer exists (e.g. it was moved or deleted)
+    ///
+    /// Remote workspaces are not checked.
+    pub fn is_missing(&self) -> bool {
+        self.remote_host.is_none()
+            && (!self.workspace_path.exists()
+                || self.config_path.as_ref().is_some_and(|path| !path.exists()))
+    }
+
+    /// Whether both entries are the same, including the fields ignored by `PartialEq`
+    fn is_same_as(&self, other: &Entry) -> bool {
+        self == other
+            && self.workspace_name == other.workspace_name
+            && self.dev_container_name == other.dev_container_name
+            && self.config_name == other.config_name
+            && self.workspace_kind == other.workspace_kind
+            && self.container_folder == other.container_folder
+            && self.last_opened == other.last_opened
+            && self.open_count == other.open_count
+            && self.recent_opens == other.recent_opens
+            && self.pinned == other.pinned
+    }
+
+    /// Adds the opens of a previous version of this entry to this one
+    pub fn add_usage(&mut self, previous: &Entry) {
+        self.open_count = self.open_count.saturating_add(previous.open_count);
+        let mut recent_opens = previous.opens();
+        recent_opens.append(&mut self.recent_opens);
+        let skip = recent_opens.len().saturating_sub(MAX_RECENT_OPENS);
+        self.recent_opens = recent_opens.split_off(skip);
+    }
+
+    /// Scores how frequently and recently this entry was opened (higher is better)
+    ///
+    /// The average weight of the recent opens, which decays with their age, multiplied by the open count.
+    pub fn frecency(&self, now: DateTime<Utc>) -> u64 {
+        let opens = self.opens();
+        let weights: u64 = opens
+            .iter()
+            .map(|opened| {
+                let age = (now - *opened).num_days();
+                FRECENCY_WEIGHTS
+                    .iter()
+                    .find(|(days, _)| age < *days)
+                    .map_or(FRECENCY_MIN_WEIGHT, |(_, weight)| *weight)
+            })
+            .sum();
+        u64::from(self.open_count) * weights / opens.len() as u64
+    }
+
+    /// The recent opens, falling back to `last_opened` for entries of older versions
+    fn opens(&self) -> Vec<DateTime<Utc>> {
+        if self.recent_opens.is_empty() {
+            vec![self.last_opened]
+        } else {
+            self.recent_opens.clone()
+        }
+    }
+}
+
 // Required by BTreeSet since it's sorted
 impl Ord for Entry {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
@@ -100,8 +201,11 @@ impl History {
         id
     }
 
-    pub fn update(&mut self, id: EntryId, entry: Entry) -> Option<Entry> {
+    /// Replaces an entry with a newly opened version of it, keeping its usage and pin
+    pub fn update(&mut self, id: EntryId, mut entry: Entry) -> Option<Entry> {
         if let std::collections::hash_map::Entry::Occupied(mut e) = self.0.entry(id) {
+            entry.add_usage(e.get());
+            entry.pinned = e.get().pinned;
             return Some(e.insert(entry));
         }
         None
@@ -111,12 +215,15 @@ impl History {
         self.0.remove(&id)
     }
 
+    /// Finds the entry equal to the given one
+    pub fn find(&self, entry: &Entry) -> Option<EntryId> {
+        self.0
+            .iter()
+            .find_map(|(id, history_entry)| (history_entry == entry).then_some(*id))
+    }
+
     pub fn upsert(&mut self, entry: Entry) -> EntryId {
-        if let Some(id) = self
-            .0
-            .iter_mut()
-            .find_map(|(id, history_entry)| (history_entry == &entry).then_some(*id))
-        {
+        if let Some(id) = self.find(&entry) {
             assert!(
                 self.update(id, entry).is_some(),
                 "Existing history entry to be replaced"
@@ -127,12 +234,80 @@ impl History {
         }
     }
 
-    pub fn iter(&self) -> std::collections::hash_map::Iter<'_, EntryId, Entry> {
-        self.0.iter()
+    /// Returns the dev container config used the last time the workspace was opened.
+    pub fn remembered_config(
+        &self,
+        workspace_path: &Path,
+        remote_host: Option<&str>,
+    ) -> Option<PathBuf> {
+        self.0
+            .values()
+            .filter(|entry| {
+                entry.workspace_path == workspace_path
+                    && entry.remote_host.as_deref() == remote_host
+            })
+            .filter(|entry| entry.config_path.is_some())
+            .max_by_key(|entry| entry.last_opened)
+            .and_then(|entry| entry.config_path.clone())
     }
 
-    pub fn into_entries(self) -> Vec<Entry> {
-        self.0.into_values().collect()
+    /// Adds an entry of another history, keeping the more recently opened one of equal entries
+    ///
+    /// Returns whether the entry was added.
+    pub fn merge(&mut self, entry: Entry) -> bool {
+        match self.find(&entry) {
+            Some(id) if self.0[&id].last_opened >= entry.last_opened => false,
+            Some(id) => {
+                self.0.insert(id, entry);
+                true
+            }
+            None => {
+                self.insert(entry);
+                true
+            }
+        }
+    }
+
+    /// Adds the opens and the pin of another entry of the same workspace to an entry
+    ///
+    /// Returns `None` if the entry does not exist.
+    pub fn combine(&mut self, id: EntryId, other: &Entry) -> Option<()> {
+        let entry = self.0.get_mut(&id)?;
+        entry.open_count = entry.open_count.saturating_add(other.open_count);
+        let mut recent_opens = entry.opens();
+        recent_opens.extend(other.opens());
+        recent_opens.sort_unstable();
+        let skip = recent_opens.len().saturating_sub(MAX_RECENT_OPENS);
+        entry.recent_opens = recent_opens.split_off(skip);
+        entry.last_opened = entry.last_opened.max(other.last_opened);
+        entry.pinned |= other.pinned;
+        Some(())
+    }
+
+    /// Pins or unpins an entry
+    ///
+    /// Returns `None` if the entry does not exist.
+    pub fn set_pinned(&mut self, id: EntryId, pinned: bool) -> Option<()> {
+        self.0.get_mut(&id)?.pinned = pinned;
+        Some(())
+    }
+
+    /// Returns the entries, the pinned ones first, then the most frequently and recently used
+    pub fn ranked(&self) -> Vec<(EntryId, &Entry)> {
+        let now = Utc::now();
+        let mut entries: Vec<(EntryId, &Entry)> =
+            self.0.iter().map(|(id, entry)| (*id, entry)).collect();
+        entries.sort_by_cached_key(|(_, entry)| {
+            (
+                std::cmp::Reverse((entry.pinned, entry.frecency(now), entry.last_opened)),
+                entry.location(),
+            )
+        });
+        entries
+    }
+
+    pub fn iter(&self) -> std::collections::hash_map::Iter<'_, EntryId, Entry> {
+        self.0.iter()
     }
 }
 
@@ -142,6 +317,12 @@ pub struct Tracker {
     path: PathBuf,
     /// The history struct
     pub history: History,
+    /// The maximum number of entries kept when storing the history
+    pub max_entries: usize,
+    /// The entries when the history was loaded, to merge the changes of other processes when storing
+    base: Vec<Entry>,
+    /// The version of the history file, if it was written by a newer version and must not be overwritten
+    newer_version: Option<u64>,
 }
 
 impl Tracker {
@@ -156,45 +337,63 @@ impl Tracker {
                 return Ok(Tracker {
                     path,
                     history: History::default(),
+                    max_entries: MAX_HISTORY_ENTRIES,
+                    base: Vec::new(),
+                    newer_version: None,
                 });
             }
 
-            let file = File::open(&path)?;
-            match serde_json::from_reader::<_, Vec<Entry>>(file) {
-                Ok(entries) => {
-                    debug!("Imported {:?} history entries", entries.len());
+            let content = fs::read_to_string(&path)
+                .wrap_err_with(|| format!("Could not read history file `{}`", path.display()))?;
+            match schema::parse(&content) {
+                Ok(parsed) if parsed.version > schema::VERSION => {
+                    warn!(
+                        "{}, it is left unchanged and no history is recorded until vscli is updated",
+                        newer_version(&path, parsed.version)
+                    );
+                    Ok(Tracker {
+                        path,
+                        history: History::default(),
+                        max_entries: MAX_HISTORY_ENTRIES,
+                        base: Vec::new(),
+                        newer_version: Some(parsed.version),
+                    })
+                }
+                Ok(parsed) => {
+                    debug!(
+                        "Imported {:?} history entries of version {}",
+                        parsed.entries.len(),
+                        parsed.version
+                    );
+                    if !parsed.skipped.is_empty() {
+                        recover(&path, &content, &parsed)?;
+                    }
 
                     Ok(Tracker {
                         path,
-                        history: History::from_entries(entries),
+                        history: History::from_entries(parsed.entries.clone()),
+                        max_entries: MAX_HISTORY_ENTRIES,
+                        base: parsed.entries,
+                        newer_version: None,
                     })
                 }
                 Err(err) => {
                     // ignore parsing errors
                     // move the file and start from scratch
-
-                    // find a non-existent backup file
-                    let new_path = (0..10_000) // Set an upper limit of filename checks.
-                        .map(|i| path.with_file_name(format!(".history_{i}.json.bak")))
-                        .find(|path| !path.exists())
-                        .unwrap_or_else(|| path.with_file_name(".history.json.bak"));
-
-                    fs::rename(&path, &new_path).wrap_err_with(|| {
-                        format!(
-                            "Could not move history file from `{}` to `{}`",
-                            path.display(),
+                    match move_broken(&path, &content)? {
+                        Some(new_path) => warn!(
+                            "Could not read history file: {err}\nMoved broken file to `{}`",
                             new_path.display()
-                        )
-                    })?;
-
-                    warn!(
-                        "Could not read history file: {err}\nMoved broken file to `{}`",
-                        new_path.display()
-                    );
+                        ),
+                        None => warn!("Could not read history file: {err}"),
+                    }
 
                     Ok(Tracker {
                         path,
                         history: History::default(),
+                        max_entries: MAX_HISTORY_ENTRIES,
+                        base: Vec::new(),
+                        newer_version: None,
                     })
                 }
             }
@@ -204,27 +403,194 @@ impl Tracker {
         load_inner(path)
     }
 
-    /// Saves the history, guaranteeing a size of `MAX_HISTORY_ENTRIES`
+    /// Fails if the history file was written by a newer version, so that changes to it would be lost
+    pub fn check_writable(&self) -> Result<()> {
+        if let Some(version) = self.newer_version {
+            bail!(
+                "{}, please update vscli to change it",
+                newer_version(&self.path, version)
+            );
+        }
+        Ok(())
+    }
+
+    /// Saves the history, guaranteeing a size of at most `max_entries` (or the number of pinned entries)
+    ///
+    /// The unpinned entries with the lowest frecency are dropped.
+    /// The changes are merged with the changes other processes stored since the history was loaded,
+    /// while holding a lock on the history file.
+    /// History files written by a newer version are left unchanged.
     pub fn store(self) -> Result<()> {
+        if self.newer_version.is_some() {
+            debug!("Not storing the history, the history file was written by a newer version");
+            return Ok(());
+        }
         fs::create_dir_all(
             self.path
                 .parent()
                 .ok_or_else(|| eyre!("Parent directory not found"))?,
         )?;
-        let file = File::create(self.path)?;
+        let _lock = lock(&self.path)?;
 
-        // Sort entries by last_opened (most recent first), then keep only the newest MAX_HISTORY_ENTRIES
-        let mut entries: Vec<Entry> = self.history.into_entries();
-        entries.sort_by_key(|b| std::cmp::Reverse(b.last_opened));
-        entries.truncate(MAX_HISTORY_ENTRIES);
+        let Some(disk) = read_current(&self.path)? else {
+            return Ok(());
+        };
+        let history = merge::merge(disk, &self.base, self.history);
+
+        // Sort entries by frecency (pinned first), then keep only the top `max_entries` and all pinned ones
+        let entries: Vec<&Entry> = history
+            .ranked()
+            .into_iter()
+            .enumerate()
+            .filter(|(index, (_, entry))| *index < self.max_entries || entry.pinned)
+            .map(|(_, (_, entry))| entry)
+            .collect();
+
+        write_atomic(&self.path, &entries)
+    }
+}
 
-        serde_json::to_writer_pretty(file, &entries)?;
-        Ok(())
+/// Locks the history file (through a `.lock` file next to it) until the returned file is dropped
+fn lock(path: &Path) -> Result<File> {
+    let lock_path = sibling(path, "", ".lock");
+    let file = File::create(&lock_path)
+        .wrap_err_with(|| format!("Could not create lock file `{}`", lock_path.display()))?;
+    file.lock()
+        .wrap_err_with(|| format!("Could not lock `{}`", lock_path.display()))?;
+    Ok(file)
+}
+
+/// Moves the unreadable entries of a history file to a backup file
+fn recover(path: &Path, content: &str, parsed: &schema::Parsed) -> Result<()> {
+    let _lock = lock(path)?;
+    backup_skipped(path, &parsed.skipped)?;
+
+    // another process may have stored the history (without the unreadable entries) in the meantime
+    if fs::read_to_string(path).is_ok_and(|current| current == content) {
+        let entries: Vec<&Entry> = parsed.entries.iter().collect();
+        write_atomic(path, &entries)?;
     }
+    Ok(())
+}
+
+/// Moves a history file that can not be read to a backup file, returning the path of the backup
+///
+/// The file is left alone if another process has stored the history in the meantime.
+fn move_broken(path: &Path, content: &str) -> Result<Option<PathBuf>> {
+    let _lock = lock(path)?;
+    if !fs::read_to_string(path).is_ok_and(|current| current == content) {
+        return Ok(None);
+    }
+    backup_broken(path).map(Some)
+}
+
+/// Saves the unreadable entries of a history file to a backup file, while the history is locked
+fn backup_skipped(path: &Path, skipped: &[serde_json::Value]) -> Result<()> {
+    let backup_path = backup_path(path);
+    fs::write(&backup_path, serde_json::to_string_pretty(skipped)?)
+        .wrap_err_with(|| format!("Could not write `{}`", backup_path.display()))?;
+    warn!(
+        "Skipped {} unreadable history entries, saved them to `{}`",
+        skipped.len(),
+        backup_path.display()
+    );
+    Ok(())
+}
+
+/// Moves a history file that can not be read to a backup file, while the history is locked
+fn backup_broken(path: &Path) -> Result<PathBuf> {
+    let new_path = backup_path(path);
+    fs::rename(path, &new_path).wrap_err_with(|| {
+        format!(
+            "Could not move history file from `{}` to `{}`",
+            path.display(),
+            new_path.display()
+        )
+    })?;
+    Ok(new_path)
+}
+
+/// Returns a non-existent backup file next to the history file
+fn backup_path(path: &Path) -> PathBuf {
+    (0..10_000) // Set an upper limit of filename checks.
+        .map(|i| path.with_file_name(format!(".history_{i}.json.bak")))
+        .find(|path| !path.exists())
+        .unwrap_or_else(|| path.with_file_name(".history.json.bak"))
+}
+
+/// Describes a history file written by a newer version, which would lose data when overwritten
+fn newer_version(path: &Path, version: u64) -> String {
+    format!(
+        "The history file `{}` was written by a newer version of vscli (history version {version})",
+        path.display()
+    )
+}
+
+/// Reads the entries currently stored in the history file, while the history is locked
+///
+/// A missing file has no entries, a broken file and unreadable entries are saved to a backup first.
+/// Returns `None` for a file written by a newer version, which must not be overwritten.
+fn read_current(path: &Path) -> Result<Option<Vec<Entry>>> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(Some(Vec::new())),
+        Err(err) => {
+            return Err(err)
+                .wrap_err_with(|| format!("Could not read history file `{}`", path.display()));
+        }
+    };
+    match schema::parse(&content) {
+        Ok(parsed) if parsed.version > schema::VERSION => {
+            warn!(
+                "{}, the history is not updated",
+                newer_version(path, parsed.version)
+            );
+            Ok(None)
+        }
+        Ok(parsed) => {
+            if !parsed.skipped.is_empty() {
+                backup_skipped(path, &parsed.skipped)?;
+            }
+            Ok(Some(parsed.entries))
+        }
+        Err(err) => {
+            let backup_path = backup_broken(path)?;
+            warn!(
+                "Could not read history file: {err}\nMoved broken file to `{}`",
+                backup_path.display()
+            );
+            Ok(Some(Vec::new()))
+        }
+    }
+}
+
+/// Writes the entries to a temporary file, which then replaces the history file
+///
+/// This way, the history file is never left half written.
+fn write_atomic(path: &Path, entries: &[&Entry]) -> Result<()> {
+    let temp_path = sibling(path, ".", ".tmp");
+    let mut file = File::create(&temp_path)
+        .wrap_err_with(|| format!("Could not create `{}`", temp_path.display()))?;
+    file.write_all(schema::to_string(entries)?.as_bytes())?;
+    file.sync_all()?;
+    fs::rename(&temp_path, path).wrap_err_with(|| {
+        format!(
+            "Could not move `{}` to `{}`",
+            temp_path.display(),
+            path.display()
+        )
+    })?;
+    Ok(())
+}
+
+/// Returns a path next to `path`, with a prefix and suffix added to its file name
+fn sibling(path: &Path, prefix: &str, suffix: &str) -> PathBuf {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!("{prefix}{name}{suffix}"))
 }
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use super::*;
     use crate::launch::ContainerStrategy;
     use chrono::Duration;
@@ -242,6 +608,9 @@ mod tests {
         let mut tracker = Tracker {
             path: history_path.clone(),
             history: History::default(),
+            max_entries: MAX_HISTORY_ENTRIES,
+            base: Vec::new(),
+            newer_version: None,
         };
 
         // Add 40 entries (more than MAX_HISTORY_ENTRIES = 35)
@@ -252,13 +621,20 @@ mod tests {
                 dev_container_name: None,
                 config_name: None,
                 workspace_path: PathBuf::from(format!("/path/to/workspace_{i}")),
+                workspace_kind: WorkspaceKind::Folder,
+                remote_host: None,
                 config_path: None,
+                subfolder: None,
+                container_folder: None,
                 behavior: Behavior {
                     strategy: ContainerStrategy::Detect,
                     args: vec![],
                     command: "code".to_string(),
                 },
                 last_opened: now - Duration::seconds((39 - i) * 60), // oldest first
+                open_count: 1,
+                recent_opens: vec![],
+                pinned: false,
             };
             tracker.history.insert(entry);
         }
@@ -268,7 +644,11 @@ mod tests {
 
         // Load it back
         let reloaded = Tracker::load(&history_path).unwrap();
-        let entries = reloaded.history.into_entries();
+        let entries: Vec<Entry> = reloaded
+            .history
+            .iter()
+            .map(|(_, entry)| entry.clone())
+            .collect();
 
         // Should have exactly MAX_HISTORY_ENTRIES
         assert_eq!(entries.len(), MAX_HISTORY_ENTRIES);
@@ -288,4 +668,343 @@ mod tests {
             );
         }
     }
+
+    pub(crate) fn test_entry(
+        workspace: &str,
+        config: Option<&str>,
+        last_opened: DateTime<Utc>,
+    ) -> Entry {
+        Entry {
+            workspace_name: workspace.to_string(),
+            dev_container_name: None,
+            config_name: None,
+            workspace_path: PathBuf::from(format!("/path/to/{workspace}")),
+            workspace_kind: WorkspaceKind::Folder,
+            remote_host: None,
+            config_path: config.map(PathBuf::from),
+            subfolder: None,
+            container_folder: None,
+            behavior: Behavior {
+                strategy: ContainerStrategy::Detect,
+                args: vec![],
+                command: "code".to_string(),
+            },
+            last_opened,
+            open_count: 1,
+            recent_opens: vec![last_opened],
+            pinned: false,
+        }
+    }
+
+    #[test]
+    fn test_remembered_config() {
+        let now = Utc::now();
+        let mut history = History::default();
+        history.insert(test_entry(
+            "app",
+            Some("/py.json"),
+            now - Duration::hours(2),
+        ));
+        history.insert(test_entry(
+            "app",
+            Some("/rs.json"),
+            now - Duration::hours(1),
+        ));
+        history.insert(test_entry("app", None, now));
+        history.insert(test_entry("other", Some("/go.json"), now));
+
+        let workspace = Path::new("/path/to/app");
+        assert_eq!(
+            history.remembered_config(workspace, None),
+            Some(PathBuf::from("/rs.json"))
+        );
+        assert_eq!(history.remembered_config(workspace, Some("host")), None);
+    }
+
+    #[test]
+    fn test_entry_subfolder() {
+        let mut entry = test_entry("app", Some("/rs.json"), Utc::now());
+        entry.subfolder = Some(PathBuf::from("crates/foo"));
+        entry.container_folder = Some("/workspaces/app/crates/foo".to_string());
+        let mut json = serde_json::to_value(&entry).unwrap();
+        assert_eq!(
+            serde_json::from_value::<Entry>(json.clone()).unwrap(),
+            entry
+        );
+        // the subfolder is part of the identity of an entry
+        assert_ne!(test_entry("app", Some("/rs.json"), Utc::now()), entry);
+
+        // entries of older versions have no subfolder
+        let object = json.as_object_mut().unwrap();
+        object.remove("subfolder");
+        object.remove("container_folder");
+        object.remove("open_count");
+        object.remove("recent_opens");
+        let content = serde_json::to_string(&[json]).unwrap();
+        let old = schema::parse(&content).unwrap().entries.remove(0);
+        assert_eq!(old.subfolder, None);
+        assert_eq!(old.container_folder, None);
+        assert_eq!(old.open_count, 1);
+        assert_eq!(old.frecency(old.last_opened), 100);
+    }
+
+    #[test]
+    fn test_merge() {
+        let now = Utc::now();
+        let mut history = History::default();
+        history.insert(test_entry("app", None, now));
+
+        assert!(!history.merge(test_entry("app", None, now - Duration::hours(1))));
+        assert!(history.merge(test_entry("app", None, now + Duration::hours(1))));
+        assert!(history.merge(test_entry("other", None, now)));
+
+        let entries = history.ranked();
+        assert_eq!(entries.len(), 2);
+        assert!(
+            entries
+                .iter()
+                .any(|(_, entry)| entry.last_opened == now + Duration::hours(1))
+        );
+    }
+
+    #[test]
+    fn test_pinned() {
+        let now = Utc::now();
+        let mut history = History::default();
+        let old = history.insert(test_entry("old", None, now - Duration::days(365)));
+        for i in 0..MAX_HISTORY_ENTRIES {
+            history.insert(test_entry(&format!("recent_{i}"), None, now));
+        }
+        history.set_pinned(old, true).unwrap();
+        // reopening keeps the pin
+        history.upsert(test_entry("old", None, now));
+
+        let entries = history.ranked();
+        assert_eq!(entries[0].1.workspace_name, "old");
+        assert!(entries[0].1.pinned);
+        // the last entry would be dropped without its pin
+        let (last, pinned) = entries
+            .last()
+            .map(|(id, entry)| (*id, entry.workspace_name.clone()))
+            .unwrap();
+
+        history.set_pinned(old, false).unwrap();
+        history.set_pinned(last, true).unwrap();
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("history.json");
+        let tracker = Tracker {
+            path: path.clone(),
+            history,
+            max_entries: 3,
+            base: Vec::new(),
+            newer_version: None,
+        };
+        tracker.store().unwrap();
+        let history = Tracker::load(&path).unwrap().history;
+        let entries = history.ranked();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].1.workspace_name, pinned);
+    }
+
+    #[test]
+    fn test_frecency() {
+        let now = Utc::now();
+        let mut history = History::default();
+
+        // opened weekly over the last two months
+        for week in (0..8).rev() {
+            history.upsert(test_entry(
+                "weekly",
+                None,
+                now - Duration::weeks(week) - Duration::days(2),
+            ));
+        }
+        let weekly = history
+            .iter()
+            .map(|(_, entry)| entry.clone())
+            .next()
+            .unwrap();
+        assert_eq!(history.iter().count(), 1);
+        assert_eq!(weekly.open_count, 8);
+        assert_eq!(weekly.recent_opens.len(), 8);
+        assert!(weekly.frecency(now) > test_entry("new", None, now).frecency(now));
+
+        // only the last opens are kept
+        let mut often = weekly.clone();
+        for _ in 0..MAX_RECENT_OPENS {
+            often.add_usage(&weekly);
+        }
+        assert_eq!(often.recent_opens.len(), MAX_RECENT_OPENS);
+        assert_eq!(often.recent_opens.last(), weekly.recent_opens.last());
+
+        // briefly opening many other workspaces keeps the weekly one
+        for i in 0..MAX_HISTORY_ENTRIES {
+            history.insert(test_entry(&format!("once_{i}"), None, now));
+        }
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("history.json");
+        let tracker = Tracker {
+            path: path.clone(),
+            history,
+            max_entries: MAX_HISTORY_ENTRIES,
+            base: Vec::new(),
+            newer_version: None,
+        };
+        tracker.store().unwrap();
+        let history = Tracker::load(&path).unwrap().history;
+        let entries = history.ranked();
+        assert_eq!(entries.len(), MAX_HISTORY_ENTRIES);
+        assert_eq!(entries[0].1.workspace_name, "weekly");
+    }
+
+    #[test]
+    fn test_load_skips_unreadable_entries() {
+        use tempfile::TempDir;
+
+        let temp_dir = TempDir::new().unwrap();
+        let history_path = temp_dir.path().join("history.json");
+        let entry = serde_json::to_value(test_entry("app", None, Utc::now())).unwrap();
+        let broken = serde_json::json!({ "workspace_name": "broken" });
+        fs::write(
+            &history_path,
+            serde_json::to_string(&[entry, broken]).unwrap(),
+        )
+        .unwrap();
+
+        let tracker = Tracker::load(&history_path).unwrap();
+        assert_eq!(tracker.history.iter().count(), 1);
+
+        // the broken entry is moved to a backup, and the file is migrated
+        let backup = fs::read_to_string(temp_dir.path().join(".history_0.json.bak")).unwrap();
+        assert!(backup.contains("broken"));
+        let parsed = schema::parse(&fs::read_to_string(&history_path).unwrap()).unwrap();
+        assert_eq!(parsed.version, schema::VERSION);
+        assert_eq!(parsed.entries.len(), 1);
+        assert!(parsed.skipped.is_empty());
+    }
+
+    #[test]
+    fn test_load_moves_broken_file() {
+        use tempfile::TempDir;
+
+        let temp_dir = TempDir::new().unwrap();
+        let history_path = temp_dir.path().join("history.json");
+        fs::write(&history_path, "[{").unwrap();
+
+        let tracker = Tracker::load(&history_path).unwrap();
+        assert_eq!(tracker.history.iter().count(), 0);
+        assert!(!history_path.exists());
+        let backup = temp_dir.path().join(".history_0.json.bak");
+        assert_eq!(fs::read_to_string(&backup).unwrap(), "[{");
+
+        // a file stored by another process in the meantime is kept
+        let stored = schema::to_string(&[&test_entry("app", None, Utc::now())]).unwrap();
+        fs::write(&history_path, &stored).unwrap();
+        assert_eq!(move_broken(&history_path, "[{").unwrap(), None);
+        assert_eq!(fs::read_to_string(&history_path).unwrap(), stored);
+    }
+
+    #[test]
+    fn test_load_newer_version() {
+        use tempfile::TempDir;
+
+        let temp_dir = TempDir::new().unwrap();
+        let history_path = temp_dir.path().join("history.json");
+        let content = r#"{ "version": 99, "entries": [{ "future": true }] }"#;
+        fs::write(&history_path, content).unwrap();
+
+        // the history can be used, but is not recorded
+        let mut tracker = Tracker::load(&history_path).unwrap();
+        assert_eq!(tracker.history.iter().count(), 0);
+        assert!(tracker.check_writable().is_err());
+        tracker.history.insert(test_entry("app", None, Utc::now()));
+        tracker.store().unwrap();
+        assert_eq!(fs::read_to_string(&history_path).unwrap(), content);
+
+        // also when another process wrote the newer version in the meantime
+        let mut tracker = Tracker::load(temp_dir.path().join("other.json")).unwrap();
+        tracker.check_writable().unwrap();
+        tracker.path.clone_from(&history_path);
+        tracker.history.insert(test_entry("app", None, Utc::now()));
+        tracker.store().unwrap();
+        assert_eq!(fs::read_to_string(&history_path).unwrap(), content);
+    }
+
+    #[test]
+    fn test_combine() {
+        let now = Utc::now();
+        let mut history = History::default();
+        let mut entry = test_entry("app", None, now - Duration::days(1));
+        entry.open_count = 2;
+        entry.recent_opens = vec![now - Duration::days(3), now - Duration::days(1)];
+        let id = history.insert(entry);
+
+        let mut other = test_entry("old-app", None, now - Duration::days(2));
+        other.pinned = true;
+        assert_eq!(history.combine(id, &other), Some(()));
+        let combined = &history.0[&id];
+        assert_eq!(combined.open_count, 3);
+        assert_eq!(
+            combined.recent_opens,
+            [
+                now - Duration::days(3),
+                now - Duration::days(2),
+                now - Duration::days(1)
+            ]
+        );
+        assert_eq!(combined.last_opened, now - Duration::days(1));
+        assert!(combined.pinned);
+
+        assert_eq!(history.combine(EntryId::new(), &other), None);
+    }
+
+    #[test]
+    fn test_store_keeps_unreadable_history() {
+        use tempfile::TempDir;
+
+        let temp_dir = TempDir::new().unwrap();
+        let history_path = temp_dir.path().join("history.json");
+        let backup = |i: usize| temp_dir.path().join(format!(".history_{i}.json.bak"));
+        let names = || -> Vec<String> {
+            let mut names: Vec<String> = Tracker::load(&history_path)
+                .unwrap()
+                .history
+                .iter()
+                .map(|(_, entry)| entry.workspace_name.clone())
+                .collect();
+            names.sort();
+            names
+        };
+
+        // another process wrote a broken file, it is saved before being overwritten
+        let mut tracker = Tracker::load(&history_path).unwrap();
+        tracker.history.insert(test_entry("app", None, Utc::now()));
+        fs::write(&history_path, "[{").unwrap();
+        tracker.store().unwrap();
+        assert_eq!(fs::read_to_string(backup(0)).unwrap(), "[{");
+        assert_eq!(names(), ["app"]);
+
+        // the same for unreadable entries
+        let mut tracker = Tracker::load(&history_path).unwrap();
+        tracker.history.insert(test_entry("new", None, Utc::now()));
+        let other = serde_json::to_value(test_entry("other", None, Utc::now())).unwrap();
+        let broken = serde_json::json!({ "workspace_name": "broken" });
+        fs::write(
+            &history_path,
+            serde_json::to_string(&[other, broken]).unwrap(),
+        )
+        .unwrap();
+        tracker.store().unwrap();
+        assert!(fs::read_to_string(backup(1)).unwrap().contains("broken"));
+        assert_eq!(names(), ["new", "other"]);
+
+        // other errors than a missing file fail
+        let dir_path = temp_dir.path().join("dir.json");
+        let mut tracker = Tracker::load(&dir_path).unwrap();
+        fs::create_dir(&dir_path).unwrap();
+        tracker.history.insert(test_entry("app", None, Utc::now()));
+        assert!(tracker.store().is_err());
+        assert!(dir_path.is_dir());
+    }
 }
@@ -0,0 +1,470 @@
+use super::{Entry, EntryId, History, Tracker, schema};
+use crate::opts::{HistoryAction, HistoryFilter};
+use crate::ui;
+use chrono::{DateTime, Duration, Local, Utc};
+use color_eyre::eyre::{Result, WrapErr, bail};
+use log::info;
+use serde::Serialize;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// A history entry together with its index, as printed by `history list --json`.
+#[derive(Serialize)]
+struct Listed<'a> {
+    index: usize,
+    #[serde(flatten)]
+    entry: &'a Entry,
+}
+
+impl HistoryFilter {
+    fn is_empty(&self) -> bool {
+        self.name.is_none()
+            && self.config.is_none()
+            && self.editor.is_none()
+            && self.strategy.is_none()
+            && !self.pinned
+            && !self.missing
+    }
+
+    fn matches(&self, entry: &Entry) -> bool {
+        let contains = |value: Option<&str>, text: &str| {
+            value.is_some_and(|value| value.to_lowercase().contains(&text.to_lowercase()))
+        };
+        self.name
+            .as_deref()
+            .is_none_or(|name| contains(Some(&entry.workspace_name), name))
+            && self.config.as_deref().is_none_or(|config| {
+                contains(entry.config_name.as_deref(), config)
+                    || contains(entry.dev_container_name.as_deref(), config)
+            })
+            && self
+                .editor
+                .as_deref()
+                .is_none_or(|editor| entry.behavior.command.eq_ignore_ascii_case(editor))
+            && self
+                .strategy
+                .is_none_or(|strategy| entry.behavior.strategy == strategy)
+            && (!self.pinned || entry.pinned)
+            && (!self.missing || entry.is_missing())
+    }
+}
+
+/// Returns the entries with their index (starting at 1), in the order of the recent UI.
+fn listed(history: &History) -> Vec<(usize, EntryId, &Entry)> {
+    history
+        .ranked()
+        .into_iter()
+        .enumerate()
+        .map(|(index, (id, entry))| (index + 1, id, entry))
+        .collect()
+}
+
+/// Finds the entries referred to by an index or a workspace path.
+fn find_targets<'a>(
+    listed: &[(usize, EntryId, &'a Entry)],
+    target: &str,
+) -> Result<Vec<(usize, EntryId, &'a Entry)>> {
+    if let Ok(index) = target.parse::<usize>() {
+        let Some(found) = listed.iter().find(|(i, ..)| *i == index) else {
+            bail!("No history entry #{index} found.");
+        };
+        return Ok(vec![*found]);
+    }
+
+    let path = std::fs::canonicalize(target).unwrap_or_else(|_| PathBuf::from(target));
+    let found: Vec<_> = listed
+        .iter()
+        .filter(|(_, _, entry)| entry.workspace_path == path || entry.location() == target)
+        .copied()
+        .collect();
+    if found.is_empty() {
+        bail!("No history entry found for `{target}`.");
+    }
+    Ok(found)
+}
+
+/// Finds the entries referred to by several indices or workspace paths, without duplicates.
+fn find_all_targets(history: &History, targets: &[String]) -> Result<Vec<(usize, EntryId, Entry)>> {
+    let listed = listed(history);
+    let mut entries: Vec<(usize, EntryId, Entry)> = Vec::new();
+    for target in targets {
+        for (index, id, entry) in find_targets(&listed, target)? {
+            if !entries.iter().any(|(_, other, _)| *other == id) {
+                entries.push((index, id, entry.clone()));
+            }
+        }
+    }
+    Ok(entries)
+}
+
+fn format_time(time: DateTime<Utc>) -> String {
+    DateTime::<Local>::from(time)
+        .format("%Y-%m-%d %H:%M:%S")
+        .to_string()
+}
+
+fn print_json<T: Serialize>(out: &mut impl Write, value: &T) -> Result<()> {
+    let json = serde_json::to_string_pretty(value)?;
+    ignore_broken_pipe(writeln!(out, "{json}"))
+}
+
+/// Ignores a closed stdout, e.g. when the output is piped into `head`.
+fn ignore_broken_pipe(result: io::Result<()>) -> Result<()> {
+    match result {
+        Err(err) if err.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+        result => Ok(result?),
+    }
+}
+
+/// Marks entries whose workspace or config no longer exists.
+fn missing_marker(entry: &Entry) -> &'static str {
+    if entry.is_missing() { " (missing)" } else { "" }
+}
+
+fn print_entries(out: &mut impl Write, entries: &[(usize, EntryId, &Entry)]) -> io::Result<()> {
+    let index_width = entries
+        .iter()
+        .map(|(index, ..)| index.to_string().len())
+        .max()
+        .unwrap_or(1)
+        .max(1);
+    let name_width = entries
+        .iter()
+        .map(|(_, _, entry)| entry.workspace_name.len())
+        .max()
+        .unwrap_or(9)
+        .max(9);
+    let container_width = entries
+        .iter()
+        .map(|(_, _, entry)| entry.dev_container_name.as_deref().unwrap_or("-").len())
+        .max()
+        .unwrap_or(13)
+        .max(13);
+    let editor_width = entries
+        .iter()
+        .map(|(_, _, entry)| entry.behavior.command.len())
+        .max()
+        .unwrap_or(6)
+        .max(6);
+    writeln!(
+        out,
+        "{:>index_width$}   {:<name_width$}  {:<container_width$}  {:<editor_width$}  {:<19}  PATH",
+        "#", "WORKSPACE", "DEV CONTAINER", "EDITOR", "LAST OPENED"
+    )?;
+    for (index, _, entry) in entries {
+        writeln!(
+            out,
+            "{index:>index_width$}{}  {:<name_width$}  {:<container_width$}  {:<editor_width$}  {:<19}  {}{}",
+            if entry.pinned { "*" } else { " " },
+            entry.workspace_name,
+            entry.dev_container_name.as_deref().unwrap_or("-"),
+            entry.behavior.command,
+            format_time(entry.last_opened),
+            entry.location(),
+            missing_marker(entry),
+        )?;
+    }
+    Ok(())
+}
+
+fn print_details(out: &mut impl Write, index: usize, entry: &Entry) -> io::Result<()> {
+    let optional = |value: Option<String>| value.unwrap_or_else(|| "-".to_string());
+    let args = entry
+        .behavior
+        .args
+        .iter()
+        .map(|arg| arg.to_string_lossy())
+        .collect::<Vec<_>>()
+        .join(" ");
+    writeln!(out, "#{index} {}", entry.workspace_name)?;
+    writeln!(
+        out,
+        "  location:         {}{}",
+        entry.location(),
+        missing_marker(entry)
+    )?;
+    writeln!(
+        out,
+        "  subfolder:        {}",
+        optional(entry.subfolder.as_ref().map(|p| p.display().to_string()))
+    )?;
+    writeln!(
+        out,
+        "  dev container:    {}",
+        optional(entry.dev_container_name.clone())
+    )?;
+    writeln!(
+        out,
+        "  config path:      {}",
+        optional(entry.config_path.as_ref().map(|p| p.display().to_string()))
+    )?;
+    writeln!(
+        out,
+        "  config name:      {}",
+        optional(entry.config_name.clone())
+    )?;
+    writeln!(
+        out,
+        "  container folder: {}",
+        optional(entry.container_folder.clone())
+    )?;
+    writeln!(out, "  strategy:         {}", entry.behavior.strategy)?;
+    writeln!(out, "  editor:           {}", entry.behavior.command)?;
+    writeln!(out, "  args:             {args}")?;
+    writeln!(
+        out,
+        "  last opened:      {}",
+        format_time(entry.last_opened)
+    )?;
+    writeln!(out, "  open count:       {}", entry.open_count)?;
+    writeln!(out, "  pinned:           {}", entry.pinned)
+}
+
+/// Removes the given entries and stores the history, or only reports them in a dry run.
+fn remove(mut tracker: Tracker, entries: &[(usize, EntryId, Entry)], dry_run: bool) -> Result<()> {
+    tracker.check_writable()?;
+    if entries.is_empty() {
+        info!("No history entries to remove");
+        return Ok(());
+    }
+    for (index, id, entry) in entries {
+        if dry_run {
+            info!("Would remove #{index} {}", entry.location());
+        } else {
+            tracker.history.delete(*id);
+            info!("Removed #{index} {}", entry.location());
+        }
+    }
+    if dry_run {
+        return Ok(());
+    }
+    tracker.store()
+}
+
+/// Pins or unpins the given entries and stores the history, or only reports them in a dry run.
+fn pin(mut tracker: Tracker, targets: &[String], pinned: bool, dry_run: bool) -> Result<()> {
+    tracker.check_writable()?;
+    let (action, done) = if pinned {
+        ("pin", "Pinned")
+    } else {
+        ("unpin", "Unpinned")
+    };
+    for (index, id, entry) in find_all_targets(&tracker.history, targets)? {
+        if dry_run {
+            info!("Would {action} #{index} {}", entry.location());
+        } else {
+            tracker.history.set_pinned(id, pinned);
+            info!("{done} #{index} {}", entry.location());
+        }
+    }
+    if dry_run {
+        return Ok(());
+    }
+    tracker.store()
+}
+
+fn print_listed_json(out: &mut impl Write, entries: &[(usize, EntryId, &Entry)]) -> Result<()> {
+    let listed: Vec<Listed> = entries
+        .iter()
+        .map(|(index, _, entry)| Listed {
+            index: *index,
+            entry,
+        })
+        .collect();
+    print_json(out, &listed)
+}
+
+fn clear(mut tracker: Tracker, yes: bool, dry_run: bool) -> Result<()> {
+    tracker.check_writable()?;
+    let count = tracker.history.iter().count();
+    if dry_run {
+        info!("Would remove {count} history entries");
+        return Ok(());
+    }
+    if !yes {
+        if !ui::is_interactive() {
+            bail!(
+                "Can't ask for confirmation without a terminal, pass `--yes` to remove all history entries"
+            );
+        }
+        if !ui::confirm(&format!("Remove all {count} history entries?"))? {
+            return Ok(());
+        }
+    }
+    tracker.history = History::default();
+    tracker.store()?;
+    info!("Removed {count} history entries");
+    Ok(())
+}
+
+fn export(out: &mut impl Write, history: &History, path: Option<PathBuf>) -> Result<()> {
+    let entries: Vec<&Entry> = history
+        .ranked()
+        .into_iter()
+        .map(|(_, entry)| entry)
+        .collect();
+    let content = schema::to_string(&entries)?;
+    let Some(path) = path else {
+        return ignore_broken_pipe(writeln!(out, "{content}"));
+    };
+    fs::write(&path, content)
+        .wrap_err_with(|| format!("Failed to write export file: {}", path.display()))?;
+    info!(
+        "Exported {} history entries to {}",
+        entries.len(),
+        path.display()
+    );
+    Ok(())
+}
+
+fn import(mut tracker: Tracker, path: &Path, replace: bool, dry_run: bool) -> Result<()> {
+    tracker.check_writable()?;
+    let entries = read_entries(path)?;
+    let count = entries.len();
+    let message = if replace {
+        tracker.history = History::from_entries(entries);
+        format!("Replaced the history with {count} entries")
+    } else {
+        let added = entries
+            .into_iter()
+            .filter(|entry| tracker.history.merge(entry.clone()))
+            .count();
+        format!("Imported {added} of {count} entries (the others are already in the history)")
+    };
+    if dry_run {
+        info!("Dry run: {message}");
+        return Ok(());
+    }
+    tracker.store()?;
+    info!("{message}");
+    Ok(())
+}
+
+/// Runs a `vscli history` subcommand.
+pub fn run_command(action: HistoryAction, tracker: Tracker, dry_run: bool) -> Result<()> {
+    let mut out = io::stdout().lock();
+    match action {
+        HistoryAction::List { filter, json } => {
+            let entries: Vec<_> = listed(&tracker.history)
+                .into_iter()
+                .filter(|(_, _, entry)| filter.matches(entry))
+                .collect();
+            if json {
+                print_listed_json(&mut out, &entries)?;
+            } else if entries.is_empty() {
+                ignore_broken_pipe(writeln!(out, "(no history entries)"))?;
+            } else {
+                ignore_broken_pipe(print_entries(&mut out, &entries))?;
+            }
+        }
+        HistoryAction::Show { target, json } => {
+            let listed = listed(&tracker.history);
+            let entries = find_targets(&listed, &target)?;
+            if json {
+                print_listed_json(&mut out, &entries)?;
+            } else {
+                for (index, _, entry) in entries {
+                    ignore_broken_pipe(print_details(&mut out, index, entry))?;
+                }
+            }
+        }
+        HistoryAction::Rm { targets } => {
+            let entries = find_all_targets(&tracker.history, &targets)?;
+            remove(tracker, &entries, dry_run)?;
+        }
+        HistoryAction::Pin { targets } => pin(tracker, &targets, true, dry_run)?,
+        HistoryAction::Unpin { targets } => pin(tracker, &targets, false, dry_run)?,
+        HistoryAction::Prune {
+            mut filter,
+            older_than,
+        } => {
+            if filter.is_empty() && older_than.is_none() {
+                // entries of moved or deleted workspaces
+                filter.missing = true;
+            }
+            let cutoff = older_than.map(|days| Utc::now() - Duration::days(i64::from(days)));
+            let entries: Vec<_> = listed(&tracker.history)
+                .into_iter()
+                .filter(|(_, _, entry)| filter.matches(entry))
+                .filter(|(_, _, entry)| cutoff.is_none_or(|cutoff| entry.last_opened < cutoff))
+                .map(|(index, id, entry)| (index, id, entry.clone()))
+                .collect();
+            remove(tracker, &entries, dry_run)?;
+        }
+        HistoryAction::Clear { yes } => clear(tracker, yes, dry_run)?,
+        HistoryAction::Export { path } => export(&mut out, &tracker.history, path)?,
+        HistoryAction::Import { path, replace } => import(tracker, &path, replace, dry_run)?,
+    }
+    Ok(())
+}
+
+/// Reads exported history entries from a file, or stdin for `-`.
+fn read_entries(path: &Path) -> Result<Vec<Entry>> {
+    let content = if path == Path::new("-") {
+        io::read_to_string(io::stdin().lock()).wrap_err("Failed to read stdin")?
+    } else {
+        fs::read_to_string(path)
+            .wrap_err_with(|| format!("Failed to read import file: {}", path.display()))?
+    };
+    let parsed = schema::parse(&content)
+        .wrap_err_with(|| format!("Failed to parse the history entries of {}", path.display()))?;
+    if parsed.version > schema::VERSION {
+        bail!(
+            "{} was exported by a newer version of vscli (history version {}), please update vscli",
+            path.display(),
+            parsed.version
+        );
+    }
+    Ok(parsed.entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::tests::test_entry;
+    use crate::launch::ContainerStrategy;
+
+    #[test]
+    fn test_filter_and_targets() {
+        let now = Utc::now();
+        let mut history = History::default();
+        let mut api = test_entry("api", Some("/api.json"), now);
+        api.dev_container_name = Some("Rust".to_string());
+        api.behavior.command = "cursor".to_string();
+        history.insert(api);
+        history.insert(test_entry("web", None, now - Duration::days(1)));
+
+        let filter = HistoryFilter {
+            config: Some("rust".to_string()),
+            editor: Some("Cursor".to_string()),
+            ..HistoryFilter::default()
+        };
+        let listed = listed(&history);
+        let matching: Vec<_> = listed
+            .iter()
+            .filter(|(_, _, entry)| filter.matches(entry))
+            .map(|(index, _, entry)| (*index, entry.workspace_name.as_str()))
+            .collect();
+        assert_eq!(matching, [(1, "api")]);
+
+        let filter = HistoryFilter {
+            strategy: Some(ContainerStrategy::ForceClassic),
+            ..HistoryFilter::default()
+        };
+        assert!(!listed.iter().any(|(_, _, entry)| filter.matches(entry)));
+
+        assert_eq!(
+            find_targets(&listed, "2").unwrap()[0].2.workspace_name,
+            "web"
+        );
+        assert_eq!(
+            find_targets(&listed, "/path/to/api").unwrap()[0]
+                .2
+                .workspace_name,
+            "api"
+        );
+        assert!(find_targets(&listed, "3").is_err());
+        assert!(find_targets(&listed, "/path/to/nothing").is_err());
+    }
+}
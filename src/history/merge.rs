@@ -0,0 +1,125 @@
+use super::{Entry, History, MAX_RECENT_OPENS};
+
+/// Merges the changes made to a history since it was loaded into the current history file.
+///
+/// `disk` is the current content of the file, which may have been changed by other processes,
+/// `base` the content when it was loaded and `ours` the changed history.
+/// Entries removed from `ours` are removed, entries added or changed in `ours` are added or combined
+/// with the version on disk, and all other entries on disk are kept as they are.
+pub(super) fn merge(disk: Vec<Entry>, base: &[Entry], ours: History) -> History {
+    let mut merged = History::from_entries(disk);
+
+    for old in base {
+        if !ours.0.values().any(|entry| entry == old)
+            && let Some(id) = merged.find(old)
+        {
+            merged.delete(id);
+        }
+    }
+
+    for entry in ours.0.into_values() {
+        let base_version = base.iter().find(|old| **old == entry);
+        if base_version.is_some_and(|old| old.is_same_as(&entry)) {
+            // not changed by us, so the version on disk is the most recent one
+            continue;
+        }
+        match merged.find(&entry) {
+            Some(id) => {
+                let theirs = &merged.0[&id];
+                let combined = combine(entry, theirs, base_version);
+                merged.0.insert(id, combined);
+            }
+            None => {
+                merged.insert(entry);
+            }
+        }
+    }
+    merged
+}
+
+/// Combines two versions of an entry that were changed concurrently.
+fn combine(ours: Entry, theirs: &Entry, base: Option<&Entry>) -> Entry {
+    if base.is_some_and(|base| base.is_same_as(theirs)) {
+        return ours;
+    }
+
+    // both were opened since the base version, so the opens of both count
+    let base_count = base.map_or(0, |base| base.open_count);
+    let open_count = ours
+        .open_count
+        .saturating_add(theirs.open_count)
+        .saturating_sub(base_count)
+        .max(1);
+    let mut recent_opens = ours.recent_opens.clone();
+    recent_opens.extend(theirs.recent_opens.iter().copied());
+    recent_opens.sort();
+    recent_opens.dedup();
+    let skip = recent_opens.len().saturating_sub(MAX_RECENT_OPENS);
+    let recent_opens = recent_opens.split_off(skip);
+    let pinned = if base.is_some_and(|base| base.pinned == ours.pinned) {
+        theirs.pinned
+    } else {
+        ours.pinned
+    };
+
+    let newer = if theirs.last_opened > ours.last_opened {
+        theirs.clone()
+    } else {
+        ours
+    };
+    Entry {
+        open_count,
+        recent_opens,
+        pinned,
+        ..newer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::tests::test_entry;
+    use chrono::{Duration, Utc};
+
+    #[test]
+    fn test_merge_concurrent_changes() {
+        let now = Utc::now();
+        let base = vec![
+            test_entry("kept", None, now - Duration::days(2)),
+            test_entry("removed", None, now - Duration::days(2)),
+            test_entry("opened", None, now - Duration::days(2)),
+        ];
+
+        // another process opened `opened` and added `theirs`
+        let mut disk = base.clone();
+        let mut opened = test_entry("opened", None, now - Duration::hours(1));
+        opened.add_usage(&base[2]);
+        disk[2] = opened;
+        disk.push(test_entry("theirs", None, now - Duration::hours(1)));
+
+        // we removed `removed`, opened `opened` and added `ours`
+        let mut ours = History::from_entries(base.clone());
+        let removed = ours.find(&base[1]).unwrap();
+        ours.delete(removed);
+        let opened = ours.find(&base[2]).unwrap();
+        ours.update(opened, test_entry("opened", None, now));
+        ours.insert(test_entry("ours", None, now));
+
+        let merged = merge(disk, &base, ours);
+        let mut names: Vec<&str> = merged
+            .iter()
+            .map(|(_, entry)| entry.workspace_name.as_str())
+            .collect();
+        names.sort_unstable();
+        assert_eq!(names, ["kept", "opened", "ours", "theirs"]);
+
+        let opened = merged
+            .iter()
+            .map(|(_, entry)| entry)
+            .find(|entry| entry.workspace_name == "opened")
+            .unwrap();
+        assert_eq!(opened.open_count, 3);
+        assert_eq!(opened.recent_opens.len(), 3);
+        assert_eq!(opened.last_opened, now);
+    }
+}
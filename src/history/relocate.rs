@@ -0,0 +1,151 @@
+use std::collections::{HashSet, VecDeque};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::Entry;
+use crate::workspace::{Workspace, WorkspaceKind};
+
+/// The number of parent folders above the closest existing one that are searched as well
+const SEARCH_PARENTS: usize = 2;
+/// The depth searched below each of the folders
+const SEARCH_DEPTH: usize = 3;
+/// Upper limit of read folders, so that large trees don't block the launch
+const MAX_SEARCHED_FOLDERS: usize = 5_000;
+
+/// Searches the folders around a missing workspace for a workspace with the same name,
+/// which contains its dev container config at the same place.
+///
+/// The closest matches are returned first.
+pub fn find_relocations(entry: &Entry) -> Vec<PathBuf> {
+    let Some(name) = entry.workspace_path.file_name() else {
+        return Vec::new();
+    };
+    // configs outside of the workspace can not be used to recognize it
+    let config = entry
+        .config_path
+        .as_deref()
+        .and_then(|config| config.strip_prefix(&entry.workspace_path).ok());
+    let is_match = |path: &Path, is_dir: bool| {
+        path.file_name() == Some(name)
+            && is_dir == (entry.workspace_kind == WorkspaceKind::Folder)
+            && config.is_none_or(|config| path.join(config).is_file())
+    };
+
+    let roots = entry
+        .workspace_path
+        .ancestors()
+        .skip(1)
+        .skip_while(|path| !path.is_dir())
+        .take(SEARCH_PARENTS + 1);
+    let mut visited = HashSet::new();
+    let mut found = Vec::new();
+    for root in roots {
+        let mut queue = VecDeque::from([(root.to_path_buf(), 0)]);
+        while let Some((folder, depth)) = queue.pop_front() {
+            if visited.len() >= MAX_SEARCHED_FOLDERS {
+                return found;
+            }
+            if !visited.insert(folder.clone()) {
+                continue;
+            }
+            let Ok(children) = fs::read_dir(&folder) else {
+                continue;
+            };
+            for child in children.flatten() {
+                let Ok(file_type) = child.file_type() else {
+                    continue;
+                };
+                let path = child.path();
+                if is_match(&path, file_type.is_dir()) && !found.contains(&path) {
+                    found.push(path.clone());
+                }
+                let hidden = child.file_name().to_string_lossy().starts_with('.');
+                if file_type.is_dir() && !hidden && depth < SEARCH_DEPTH {
+                    queue.push_back((path, depth + 1));
+                }
+            }
+        }
+    }
+    found
+}
+
+/// Returns the dev container configs of an existing workspace whose config no longer exists,
+/// to which the entry can be moved instead.
+pub fn find_config_relocations(entry: &Entry) -> Vec<PathBuf> {
+    Workspace::from_path(&entry.workspace_path)
+        .map(|workspace| workspace.find_dev_container_configs())
+        .unwrap_or_default()
+}
+
+/// Returns the entry moved to another workspace path, along with its dev container config.
+pub fn relocated(entry: &Entry, workspace_path: &Path) -> Entry {
+    let config_path = entry.config_path.as_ref().map(|config| {
+        config
+            .strip_prefix(&entry.workspace_path)
+            .map_or_else(|_| config.clone(), |config| workspace_path.join(config))
+    });
+    Entry {
+        workspace_path: workspace_path.to_path_buf(),
+        config_path,
+        ..entry.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::tests::test_entry;
+    use chrono::Utc;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_find_relocations() {
+        let temp_dir = TempDir::new().unwrap();
+        // nested, so that the searched parent folders are inside the temporary directory
+        let home = temp_dir.path().join("home");
+        let root = home.join("user");
+        let config = Path::new(".devcontainer/devcontainer.json");
+        for workspace in [root.join("projects/app"), home.join("archive/2024/app")] {
+            let config = workspace.join(config);
+            fs::create_dir_all(config.parent().unwrap()).unwrap();
+            fs::write(config, "{}").unwrap();
+        }
+        // same name, but without the config
+        fs::create_dir_all(root.join("other/app")).unwrap();
+
+        let old_path = root.join("old/app");
+        let mut entry = test_entry("app", None, Utc::now());
+        entry.workspace_path.clone_from(&old_path);
+        entry.config_path = Some(old_path.join(config));
+        assert!(entry.is_missing());
+
+        let found = find_relocations(&entry);
+        assert_eq!(
+            found,
+            [root.join("projects/app"), home.join("archive/2024/app")]
+        );
+
+        let moved = relocated(&entry, &found[0]);
+        assert_eq!(moved.workspace_path, root.join("projects/app"));
+        assert_eq!(
+            moved.config_path,
+            Some(root.join("projects/app").join(config))
+        );
+        assert!(!moved.is_missing());
+    }
+
+    #[test]
+    fn test_find_config_relocations() {
+        let temp_dir = TempDir::new().unwrap();
+        let workspace = temp_dir.path().join("app");
+        let config = workspace.join(".devcontainer/rust/devcontainer.json");
+        fs::create_dir_all(config.parent().unwrap()).unwrap();
+        fs::write(&config, "{}").unwrap();
+
+        let mut entry = test_entry("app", None, Utc::now());
+        entry.workspace_path.clone_from(&workspace);
+        entry.config_path = Some(workspace.join(".devcontainer/devcontainer.json"));
+        assert!(entry.is_missing());
+        assert_eq!(find_config_relocations(&entry), [config]);
+    }
+}
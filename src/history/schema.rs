@@ -0,0 +1,175 @@
+use color_eyre::eyre::{Result, bail};
+use log::warn;
+use serde::Serialize;
+use serde_json::{Map, Value, json};
+
+use super::Entry;
+
+/// The current version of the history file layout
+///
+/// Version 1 is the bare array of entries written by older releases,
+/// version 2 wraps the entries in an object with the version.
+pub(super) const VERSION: u64 = 2;
+
+/// Migrations of a single entry, the one at index `i` upgrades an entry from version `i + 1`
+const MIGRATIONS: [fn(&mut Map<String, Value>); 1] = [migrate_v1];
+
+/// The layout of the history file
+#[derive(Serialize)]
+struct HistoryFile<'a> {
+    version: u64,
+    entries: &'a [&'a Entry],
+}
+
+/// The content of a history file
+#[derive(Debug, Default)]
+pub(super) struct Parsed {
+    /// The version of the file
+    pub version: u64,
+    /// The entries that could be read, migrated to the current version
+    pub entries: Vec<Entry>,
+    /// The raw entries that could not be read
+    pub skipped: Vec<Value>,
+}
+
+/// Parses the content of a history file of any version up to the current one.
+///
+/// Entries that can not be read are skipped with a warning.
+/// Files of newer versions are returned without entries, as they can not be read safely.
+pub(super) fn parse(content: &str) -> Result<Parsed> {
+    let (version, raw_entries) = match serde_json::from_str(content)? {
+        Value::Array(entries) => (1, entries),
+        Value::Object(mut object) => {
+            let Some(version) = object.get("version").and_then(Value::as_u64) else {
+                bail!("The history file has no version");
+            };
+            let Some(Value::Array(entries)) = object.remove("entries") else {
+                bail!("The history file has no entries");
+            };
+            (version, entries)
+        }
+        _ => bail!("Unknown layout of the history file"),
+    };
+    if version == 0 {
+        bail!("Unknown version 0 of the history file");
+    }
+    if version > VERSION {
+        return Ok(Parsed {
+            version,
+            ..Parsed::default()
+        });
+    }
+
+    let mut parsed = Parsed {
+        version,
+        ..Parsed::default()
+    };
+    for (index, raw) in raw_entries.into_iter().enumerate() {
+        match read_entry(version, raw.clone()) {
+            Ok(entry) => parsed.entries.push(entry),
+            Err(err) => {
+                warn!("Skipping unreadable history entry {}: {err}", index + 1);
+                parsed.skipped.push(raw);
+            }
+        }
+    }
+    Ok(parsed)
+}
+
+/// Migrates an entry of `version` to the current version and reads it
+fn read_entry(version: u64, mut raw: Value) -> Result<Entry> {
+    let Some(object) = raw.as_object_mut() else {
+        bail!("Expected an object");
+    };
+    for migrate in MIGRATIONS.iter().skip(usize::try_from(version)? - 1) {
+        migrate(object);
+    }
+    Ok(serde_json::from_value(raw)?)
+}
+
+/// Serializes the entries in the current layout
+pub(super) fn to_string(entries: &[&Entry]) -> Result<String> {
+    Ok(serde_json::to_string_pretty(&HistoryFile {
+        version: VERSION,
+        entries,
+    })?)
+}
+
+/// Adds the fields introduced during version 1, which entries written before them do not have
+fn migrate_v1(entry: &mut Map<String, Value>) {
+    let recent_opens = match entry.get("last_opened") {
+        Some(last_opened) => json!([last_opened]),
+        None => json!([]),
+    };
+    let defaults = [
+        ("config_name", Value::Null),
+        ("workspace_kind", json!("folder")),
+        ("remote_host", Value::Null),
+        ("subfolder", Value::Null),
+        ("container_folder", Value::Null),
+        ("open_count", json!(1)),
+        ("recent_opens", recent_opens),
+        ("pinned", json!(false)),
+    ];
+    for (key, default) in defaults {
+        entry.entry(key).or_insert(default);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::tests::test_entry;
+    use chrono::Utc;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_roundtrip() {
+        let mut entry = test_entry("app", Some("/rs.json"), Utc::now());
+        entry.subfolder = Some(PathBuf::from("crates/foo"));
+        entry.pinned = true;
+        let parsed = parse(&to_string(&[&entry]).unwrap()).unwrap();
+        assert_eq!(parsed.version, VERSION);
+        assert!(parsed.skipped.is_empty());
+        assert_eq!(parsed.entries.len(), 1);
+        assert!(parsed.entries[0].is_same_as(&entry));
+    }
+
+    #[test]
+    fn test_migrate_bare_array() {
+        let content = r#"[
+            {
+                "workspace_name": "app",
+                "dev_container_name": null,
+                "workspace_path": "/app",
+                "config_path": null,
+                "behavior": { "strategy": "Detect", "args": [] },
+                "last_opened": "2024-05-01T10:00:00Z"
+            },
+            { "workspace_name": "broken" },
+            "not an entry"
+        ]"#;
+        let parsed = parse(content).unwrap();
+        assert_eq!(parsed.version, 1);
+        assert_eq!(parsed.skipped.len(), 2);
+        assert_eq!(parsed.entries.len(), 1);
+
+        let entry = &parsed.entries[0];
+        assert_eq!(entry.workspace_name, "app");
+        assert_eq!(entry.subfolder, None);
+        assert_eq!(entry.open_count, 1);
+        assert_eq!(entry.recent_opens, [entry.last_opened]);
+        assert!(!entry.pinned);
+    }
+
+    #[test]
+    fn test_unsupported_layouts() {
+        let newer = parse(r#"{ "version": 99, "entries": [{ "future": true }] }"#).unwrap();
+        assert_eq!(newer.version, 99);
+        assert!(newer.entries.is_empty());
+
+        assert!(parse(r#"{ "entries": [] }"#).is_err());
+        assert!(parse("42").is_err());
+        assert!(parse("[{").is_err());
+    }
+}
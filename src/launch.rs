@@ -1,15 +1,17 @@
 use std::{
     ffi::OsString,
     fmt::Display,
+    io::IsTerminal,
     path::{Path, PathBuf},
     str::FromStr,
 };
 
 use clap::ValueEnum;
 use color_eyre::eyre::{self, Result, bail, eyre};
-use log::{info, trace};
+use log::{debug, info, trace, warn};
 use serde::{Deserialize, Serialize};
 
+use crate::editor::{Editor, EditorRegistry, LaunchMode};
 use crate::workspace::{DevContainer, Workspace};
 
 pub const LAUNCH_DETECT: &str = "detect";
@@ -71,7 +73,7 @@ pub struct Behavior {
     pub strategy: ContainerStrategy,
     /// Additional arguments to pass to the editor.
     pub args: Vec<OsString>,
-    /// The editor command to use (e.g. "code", "code-insiders", "cursor")
+    /// The editor to use, as an editor id or command (e.g. "code", "code-insiders", "cursor")
     #[serde(default = "default_editor_command")]
     pub command: String,
 }
@@ -80,16 +82,52 @@ fn default_editor_command() -> String {
     "code".to_string()
 }
 
-/// Formats the editor name based on the command for display in messages.
-fn format_editor_name(command: &str) -> String {
-    match command.to_lowercase().as_str() {
-        "code" => "Visual Studio Code".to_string(),
-        "code-insiders" => "Visual Studio Code Insiders".to_string(),
-        "cursor" => "Cursor".to_string(),
-        "codium" => "VSCodium".to_string(),
-        "positron" => "Positron".to_string(),
-        _ => format!("'{command}'"),
+/// What was opened by a launch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Launched {
+    /// The dev container that was used, if any.
+    pub dev_container: Option<DevContainer>,
+    /// The opened folder relative to the workspace, if a dev container was opened at a subfolder.
+    pub subfolder: Option<PathBuf>,
+    /// The folder that was opened inside the dev container.
+    pub container_folder: Option<String>,
+    /// Whether the fallback folder was opened without a dev container, instead of the workspace.
+    pub fallback: bool,
+}
+
+impl Launched {
+    fn classic(fallback: bool) -> Self {
+        Self {
+            dev_container: None,
+            subfolder: None,
+            container_folder: None,
+            fallback,
+        }
     }
+
+    fn container(dev_container: DevContainer, subfolder: Option<PathBuf>) -> Self {
+        let container_folder = dev_container.container_folder(subfolder.as_deref());
+        Self {
+            dev_container: Some(dev_container),
+            subfolder,
+            container_folder: Some(container_folder),
+            fallback: false,
+        }
+    }
+}
+
+/// The editor arguments `customizations.vscli` can set, which only change how the window is opened.
+///
+/// A repository must not be able to run other programs through the editor, so all other arguments are ignored.
+const CUSTOMIZABLE_ARGS: [&str; 4] = ["--new-window", "-n", "--reuse-window", "-r"];
+
+/// The launch options that were set explicitly, and are not overridden by `customizations.vscli`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Explicit {
+    /// Whether the editor was set explicitly.
+    pub editor: bool,
+    /// Whether the editor arguments were set explicitly.
+    pub args: bool,
 }
 
 /// The configuration for the launch behavior
@@ -99,108 +137,462 @@ pub struct Setup {
     workspace: Workspace,
     /// The behavior configuration.
     behavior: Behavior,
+    /// The editor resolved from the behavior's command.
+    editor: Editor,
     /// Whether to perform a dry run, not actually launching the editor.
     dry_run: bool,
+    /// How to wait for the launched editor.
+    launch_mode: LaunchMode,
+    /// The editors `customizations.vscli` can choose from, and the options it must not override.
+    customizations: Option<(EditorRegistry, Explicit)>,
+    /// Selects a dev container by name or `.devcontainer/<dir>` folder, instead of asking.
+    selector: Option<String>,
+    /// The config used the last time the workspace was opened.
+    remembered: Option<PathBuf>,
+    /// Whether to open the remembered config directly, instead of asking.
+    open_remembered: bool,
+    /// The folder opened instead of the workspace when no dev container is used.
+    fallback: Option<Workspace>,
 }
 
 impl Setup {
-    pub fn new(workspace: Workspace, behavior: Behavior, dry_run: bool) -> Self {
+    pub fn new(workspace: Workspace, behavior: Behavior, editor: Editor, dry_run: bool) -> Self {
         Self {
             workspace,
             behavior,
+            editor,
             dry_run,
+            launch_mode: LaunchMode::default(),
+            customizations: None,
+            selector: None,
+            remembered: None,
+            open_remembered: false,
+            fallback: None,
         }
     }
 
+    /// Sets the config used the last time, which is preselected or (with `open`) used directly.
+    pub fn with_remembered(mut self, remembered: Option<PathBuf>, open: bool) -> Self {
+        self.remembered = remembered;
+        self.open_remembered = open;
+        self
+    }
+
+    /// Sets the folder opened instead of the workspace when no dev container is used,
+    /// e.g. the folder that was asked for when the workspace is one of its ancestors.
+    pub fn with_fallback(mut self, fallback: Option<Workspace>) -> Self {
+        self.fallback = fallback;
+        self
+    }
+
+    /// Sets how to wait for the launched editor.
+    pub fn with_launch_mode(mut self, launch_mode: LaunchMode) -> Self {
+        self.launch_mode = launch_mode;
+        self
+    }
+
+    /// Selects the dev container by its name or `.devcontainer/<dir>` folder, instead of asking.
+    pub fn with_selector(mut self, selector: Option<String>) -> Self {
+        self.selector = selector;
+        self
+    }
+
+    /// Lets the `customizations.vscli` of the dev container override the options that were not set explicitly.
+    pub fn with_customizations(mut self, editors: &EditorRegistry, explicit: Explicit) -> Self {
+        self.customizations = Some((editors.clone(), explicit));
+        self
+    }
+
+    /// Applies the `customizations.vscli` of the dev container and returns the subfolder to open.
+    fn customize(
+        &mut self,
+        dev_container: &DevContainer,
+        subfolder: Option<&Path>,
+    ) -> Option<PathBuf> {
+        let customizations = &dev_container.customizations;
+        if let Some((editors, explicit)) = &self.customizations {
+            if let Some(id) = customizations
+                .editor
+                .as_deref()
+                .filter(|_| !explicit.editor)
+            {
+                // unknown commands would let the repository run any program
+                match editors.resolve_id(id) {
+                    Some(editor) if editor.dev_containers => {
+                        debug!("Using the editor from the dev container customizations: {id}");
+                        self.editor = editor;
+                    }
+                    Some(editor) => warn!(
+                        "Ignoring the customized editor {}, since it does not support dev containers",
+                        editor.name
+                    ),
+                    None => warn!(
+                        "Ignoring the customized editor `{id}`, since it is not the id of a known editor"
+                    ),
+                }
+            }
+            if !explicit.args && !customizations.args.is_empty() {
+                let (args, ignored): (Vec<&String>, Vec<&String>) = customizations
+                    .args
+                    .iter()
+                    .partition(|arg| CUSTOMIZABLE_ARGS.contains(&arg.as_str()));
+                if !ignored.is_empty() {
+                    warn!(
+                        "Ignoring the customized editor arguments {ignored:?}, only {} can be set by the dev container",
+                        CUSTOMIZABLE_ARGS.join(", ")
+                    );
+                }
+                if !args.is_empty() {
+                    debug!("Using the editor arguments from the dev container customizations");
+                    self.behavior.args = args.into_iter().map(OsString::from).collect();
+                }
+            }
+        }
+
+        subfolder
+            .map(Path::to_path_buf)
+            .or_else(|| customizations.subfolder.as_ref().map(PathBuf::from))
+    }
+
     /// Selects the dev container that should be used.
     fn detect(&self, config: Option<PathBuf>) -> Result<Option<DevContainer>> {
-        let name = self.workspace.name.clone();
-
         if let Some(config) = config {
             let config_log = config.display();
             trace!("Dev container set by path: {config_log}");
-            Ok(Some(DevContainer::from_config(config.as_path(), &name)?))
-        } else {
-            let configs = self.workspace.find_dev_container_configs();
-            let dev_containers = self.workspace.load_dev_containers(&configs)?;
-
-            match configs.len() {
-                0 => {
-                    trace!("No dev container specified.");
-                    Ok(None)
-                }
-                1 => {
-                    trace!("Selected the only existing dev container.");
-                    Ok(dev_containers.into_iter().next())
-                }
-                _ => Ok(Some(
-                    crate::ui::pick_devcontainer(dev_containers)?
-                        .ok_or_else(|| eyre!("Dev container selection cancelled"))?,
-                )),
+            return Ok(Some(self.workspace.load_dev_container(&config)?));
+        }
+
+        let configs = self.workspace.find_dev_container_configs();
+        let dev_containers = self.workspace.load_dev_containers(&configs)?;
+
+        if let Some(selector) = &self.selector {
+            trace!("Dev container set by selector: {selector}");
+            return select_dev_container(dev_containers, selector).map(Some);
+        }
+
+        let remembered = self.remembered.as_deref().filter(|remembered| {
+            dev_containers
+                .iter()
+                .any(|dev_container| dev_container.config_path == *remembered)
+        });
+        match dev_containers.len() {
+            0 => {
+                trace!("No dev container specified.");
+                Ok(None)
+            }
+            1 => {
+                trace!("Selected the only existing dev container.");
+                Ok(dev_containers.into_iter().next())
+            }
+            _ if self.open_remembered && remembered.is_some() => {
+                trace!("Selected the remembered dev container: {remembered:?}");
+                Ok(dev_containers
+                    .into_iter()
+                    .find(|dev_container| Some(dev_container.config_path.as_path()) == remembered))
+            }
+            _ if !std::io::stdin().is_terminal() || !std::io::stdout().is_terminal() => {
+                let hint = if remembered.is_some() {
+                    " or `--remembered`"
+                } else {
+                    ""
+                };
+                bail!(
+                    "Found multiple dev containers, but can't ask which one to use without a terminal. \
+                     Select one with `--devcontainer <name|dir>`{hint}:\n{}",
+                    list_choices(&dev_containers)
+                );
             }
+            _ => Ok(Some(
+                crate::ui::pick_devcontainer(dev_containers, remembered)?
+                    .ok_or_else(|| eyre!("Dev container selection cancelled"))?,
+            )),
         }
     }
 
-    /// Launches vscode with the given configuration.
-    /// Returns the dev container that was used, if any.
-    pub fn launch(
-        self,
-        config: Option<PathBuf>,
-        subfolder: Option<&Path>,
-    ) -> Result<Option<DevContainer>> {
-        let editor_name = format_editor_name(&self.behavior.command);
+    /// Opens the fallback folder (or the workspace) without a dev container.
+    fn open_classic(self) -> Result<Launched> {
+        let fallback = self.fallback.is_some();
+        self.fallback
+            .as_ref()
+            .unwrap_or(&self.workspace)
+            .open_classic(
+                self.behavior.args,
+                self.dry_run,
+                self.launch_mode,
+                &self.editor,
+            )?;
+        Ok(Launched::classic(fallback))
+    }
 
+    /// Launches vscode with the given configuration.
+    /// Returns what was opened.
+    pub fn launch(mut self, config: Option<PathBuf>, subfolder: Option<&Path>) -> Result<Launched> {
         match self.behavior.strategy {
+            ContainerStrategy::Detect if !self.editor.dev_containers => {
+                warn!(
+                    "{} does not support dev containers, opening on host system...",
+                    self.editor.name
+                );
+                self.open_classic()
+            }
+            ContainerStrategy::ForceContainer if !self.editor.dev_containers => {
+                bail!(
+                    "{} does not support dev containers, so they can't be forced.",
+                    self.editor.name
+                );
+            }
             ContainerStrategy::Detect => {
                 let dev_container = self.detect(config)?;
 
-                if let Some(ref dev_container) = dev_container {
-                    info!("Opening dev container with {editor_name}...");
+                if let Some(dev_container) = dev_container {
+                    let subfolder = self.customize(&dev_container, subfolder);
+                    info!("Opening dev container with {}...", self.editor.name);
                     self.workspace.open(
                         self.behavior.args,
                         self.dry_run,
-                        dev_container,
-                        &self.behavior.command,
-                        subfolder,
+                        self.launch_mode,
+                        &dev_container,
+                        &self.editor,
+                        subfolder.as_deref(),
                     )?;
+                    Ok(Launched::container(dev_container, subfolder))
                 } else {
-                    info!("No dev container found, opening on host system with {editor_name}...");
-                    self.workspace.open_classic(
-                        self.behavior.args,
-                        self.dry_run,
-                        &self.behavior.command,
-                    )?;
+                    info!(
+                        "No dev container found, opening on host system with {}...",
+                        self.editor.name
+                    );
+                    self.open_classic()
                 }
-                Ok(dev_container)
             }
             ContainerStrategy::ForceContainer => {
-                let dev_container = self.detect(config)?;
-
-                if let Some(ref dev_container) = dev_container {
-                    info!("Force opening dev container with {editor_name}...");
-                    self.workspace.open(
-                        self.behavior.args,
-                        self.dry_run,
-                        dev_container,
-                        &self.behavior.command,
-                        subfolder,
-                    )?;
-                } else {
+                let Some(dev_container) = self.detect(config)? else {
                     bail!(
                         "No dev container found, but was forced to open it using dev containers."
                     );
-                }
-                Ok(dev_container)
-            }
-            ContainerStrategy::ForceClassic => {
-                info!("Opening without dev containers using {editor_name}...");
-                self.workspace.open_classic(
+                };
+
+                let subfolder = self.customize(&dev_container, subfolder);
+                info!("Force opening dev container with {}...", self.editor.name);
+                self.workspace.open(
                     self.behavior.args,
                     self.dry_run,
-                    &self.behavior.command,
+                    self.launch_mode,
+                    &dev_container,
+                    &self.editor,
+                    subfolder.as_deref(),
                 )?;
-                Ok(None)
+                Ok(Launched::container(dev_container, subfolder))
             }
+            ContainerStrategy::ForceClassic => {
+                info!(
+                    "Opening without dev containers using {}...",
+                    self.editor.name
+                );
+                self.open_classic()
+            }
+        }
+    }
+}
+
+/// Selects the dev container whose name (from the config or its display name)
+/// or `.devcontainer/<dir>` folder matches the selector.
+fn select_dev_container(dev_containers: Vec<DevContainer>, selector: &str) -> Result<DevContainer> {
+    let dir_selector = selector
+        .trim_start_matches("./")
+        .trim_start_matches(".devcontainer/")
+        .trim_end_matches('/');
+    let is_selected =
+        |name: Option<&str>| name.is_some_and(|name| name.eq_ignore_ascii_case(selector));
+    let (matches, others): (Vec<_>, Vec<_>) =
+        dev_containers.into_iter().partition(|dev_container| {
+            is_selected(dev_container.config.name.as_deref())
+                || is_selected(dev_container.name.as_deref())
+                || dev_container.config_dir_name().as_deref() == Some(dir_selector)
+        });
+
+    match matches.len() {
+        1 => Ok(matches.into_iter().next().expect("one match")),
+        0 if others.is_empty() => {
+            bail!("No dev container found, so `{selector}` can't be selected.")
+        }
+        0 => bail!(
+            "No dev container matches `{selector}`. Available dev containers:\n{}",
+            list_choices(&others)
+        ),
+        _ => bail!(
+            "`{selector}` is ambiguous, it matches multiple dev containers:\n{}",
+            list_choices(&matches)
+        ),
+    }
+}
+
+/// Lists the dev containers with their names and config paths, one per line.
+fn list_choices(dev_containers: &[DevContainer]) -> String {
+    dev_containers
+        .iter()
+        .map(|dev_container| {
+            format!(
+                "  - {} ({})",
+                dev_container.name.as_deref().unwrap_or("unnamed"),
+                dev_container.config_path.display()
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::workspace::DevContainerConfig;
+
+    fn dev_container(name: Option<&str>, config_path: &str) -> DevContainer {
+        DevContainer {
+            config_path: PathBuf::from(config_path),
+            name: name.map(ToString::to_string),
+            workspace_path_in_container: "/workspaces/project".to_string(),
+            compose: None,
+            config: DevContainerConfig {
+                name: name.map(ToString::to_string),
+                ..DevContainerConfig::default()
+            },
+            customizations: crate::workspace::VscliCustomizations::default(),
         }
     }
+
+    #[test]
+    fn test_select_dev_container() {
+        let dev_containers = vec![
+            dev_container(Some("Python"), "/p/.devcontainer/python/devcontainer.json"),
+            dev_container(Some("Rust"), "/p/.devcontainer/rust/devcontainer.json"),
+            dev_container(Some("rust"), "/p/.devcontainer/devcontainer.json"),
+        ];
+
+        let selected = select_dev_container(dev_containers.clone(), "python").unwrap();
+        assert_eq!(selected.name.as_deref(), Some("Python"));
+
+        let selected = select_dev_container(dev_containers.clone(), ".devcontainer/rust/").unwrap();
+        assert_eq!(selected.name.as_deref(), Some("Rust"));
+
+        let error = select_dev_container(dev_containers.clone(), "rust").unwrap_err();
+        assert!(error.to_string().contains("ambiguous"));
+
+        let error = select_dev_container(dev_containers, "go").unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .contains("  - Python (/p/.devcontainer/python/devcontainer.json)")
+        );
+    }
+
+    #[test]
+    fn test_select_dev_container_with_display_name() {
+        // `customizations.vscli.displayName` replaces the name that is shown
+        let mut go = dev_container(Some("Go"), "/p/.devcontainer/go/devcontainer.json");
+        go.name = Some("Go (team)".to_string());
+        let dev_containers = vec![
+            go,
+            dev_container(Some("Rust"), "/p/.devcontainer/rust/devcontainer.json"),
+        ];
+
+        let selected = select_dev_container(dev_containers.clone(), "go").unwrap();
+        assert_eq!(selected.name.as_deref(), Some("Go (team)"));
+        let selected = select_dev_container(dev_containers, "Go (Team)").unwrap();
+        assert_eq!(selected.config.name.as_deref(), Some("Go"));
+    }
+
+    #[test]
+    fn test_customize() {
+        let registry = EditorRegistry::default();
+        let setup = |explicit: Explicit| {
+            let editor = registry.resolve("code");
+            let behavior = Behavior {
+                strategy: ContainerStrategy::Detect,
+                args: vec![OsString::from("--user-arg")],
+                command: editor.command.clone(),
+            };
+            Setup::new(
+                Workspace::from_path(Path::new(".")).unwrap(),
+                behavior,
+                editor,
+                true,
+            )
+            .with_customizations(&registry, explicit)
+        };
+        let customized = |editor: &str, args: &[&str]| {
+            let mut dev_container = dev_container(Some("Rust"), "/p/.devcontainer.json");
+            dev_container.customizations.editor = Some(editor.to_string());
+            dev_container.customizations.args = args.iter().map(ToString::to_string).collect();
+            dev_container
+        };
+
+        let mut known = setup(Explicit::default());
+        known.customize(&customized("cursor", &["--new-window", "--verbose"]), None);
+        assert_eq!(known.editor.id, "cursor");
+        assert_eq!(known.behavior.args, [OsString::from("--new-window")]);
+
+        // unknown commands and other arguments could run any program
+        let mut unknown = setup(Explicit::default());
+        unknown.customize(&customized("sh", &["-c", "echo hi"]), None);
+        assert_eq!(unknown.editor.id, "code");
+        assert_eq!(unknown.behavior.args, [OsString::from("--user-arg")]);
+
+        let mut explicit = setup(Explicit {
+            editor: true,
+            args: true,
+        });
+        explicit.customize(&customized("cursor", &["--new-window"]), None);
+        assert_eq!(explicit.editor.id, "code");
+        assert_eq!(explicit.behavior.args, [OsString::from("--user-arg")]);
+    }
+
+    #[test]
+    fn test_classic_fallback() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        let folder = root.join("crates/app");
+        std::fs::create_dir_all(&folder).unwrap();
+        // the ancestor has a `.devcontainer` folder, but no config in it
+        std::fs::create_dir_all(root.join(".devcontainer")).unwrap();
+
+        // any command in the PATH works, since nothing is run in a dry run
+        let editor = EditorRegistry::default().resolve("true");
+        let launch = |editor: &Editor| {
+            let behavior = Behavior {
+                strategy: ContainerStrategy::Detect,
+                args: vec![],
+                command: editor.command.clone(),
+            };
+            Setup::new(
+                Workspace::from_path(root).unwrap(),
+                behavior,
+                editor.clone(),
+                true,
+            )
+            .with_fallback(Some(Workspace::from_path(&folder).unwrap()))
+            .launch(None, Some(Path::new("crates/app")))
+            .unwrap()
+        };
+
+        let launched = launch(&editor);
+        assert!(launched.fallback);
+        assert_eq!(launched.subfolder, None);
+
+        // the editor can't open the dev container of the ancestor
+        std::fs::write(
+            root.join(".devcontainer/devcontainer.json"),
+            r#"{ "image": "rust" }"#,
+        )
+        .unwrap();
+        let classic_editor = Editor {
+            dev_containers: false,
+            ..editor.clone()
+        };
+        assert!(launch(&classic_editor).fallback);
+
+        let launched = launch(&editor);
+        assert!(!launched.fallback);
+        assert!(launched.dev_container.is_some());
+        assert_eq!(launched.subfolder, Some(PathBuf::from("crates/app")));
+    }
 }
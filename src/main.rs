@@ -9,38 +9,44 @@
 
 mod config_store;
 mod container;
+mod editor;
 mod history;
 mod launch;
 mod opts;
+mod settings;
 mod ui;
 mod uri;
 mod workspace;
 
 use chrono::Utc;
-use clap::Parser;
-use color_eyre::eyre::{Result, WrapErr};
-use log::trace;
-use std::io::Write;
+use clap::{ArgMatches, CommandFactory, FromArgMatches};
+use color_eyre::eyre::{Result, WrapErr, bail, eyre};
+use log::{debug, info, trace};
+use std::io::{IsTerminal, Write};
 use std::path::{Path, PathBuf};
 
 use crate::config_store::ConfigStore;
-use crate::history::{Entry, Tracker};
+use crate::editor::EditorRegistry;
+use crate::history::{Entry, EntryId, Tracker};
+use crate::settings::{Settings, Source, arg_source};
 
 use crate::{
-    launch::{Behavior, Setup},
+    launch::{Behavior, ContainerStrategy, Explicit, Setup},
     opts::{Commands, LaunchArgs, Opts},
     ui::PickerOpts,
-    workspace::Workspace,
+    workspace::{Goto, Workspace},
 };
 
-fn load_tracker(history_path: Option<PathBuf>) -> Result<Tracker> {
+fn load_tracker(history_path: Option<PathBuf>, max_entries: usize) -> Result<Tracker> {
     let path = history_path.unwrap_or_else(|| {
         let mut p = dirs::data_local_dir().expect("Local data dir not found.");
         p.push("vscli");
         p.push("history.json");
         p
     });
-    Tracker::load(path)
+    let mut tracker = Tracker::load(path)?;
+    tracker.max_entries = max_entries;
+    Ok(tracker)
 }
 
 fn resolve_launch_config(config: Option<&PathBuf>, store: &ConfigStore) -> Result<Option<PathBuf>> {
@@ -78,87 +84,270 @@ fn workspace_root_from_config(
     Ok((root, sub))
 }
 
+/// Uses the nearest ancestor with a dev container config as the workspace, opening `path` as its subfolder.
+fn workspace_root_from_ancestors(path: &Path) -> (PathBuf, Option<PathBuf>) {
+    let Ok(abs) = std::fs::canonicalize(path) else {
+        return (path.to_path_buf(), None);
+    };
+    match workspace::find_dev_container_root(&abs) {
+        Some(root) if root != abs => {
+            debug!("Found a dev container config in {}", root.display());
+            let sub = abs.strip_prefix(&root).ok().map(Path::to_path_buf);
+            (root, sub)
+        }
+        _ => (path.to_path_buf(), None),
+    }
+}
+
 struct Application {
     history_path: Option<PathBuf>,
     config_store: ConfigStore,
+    editors: EditorRegistry,
+    settings: Settings,
     dry_run: bool,
 }
 
 impl Application {
     fn run(&self, command: Commands) -> Result<()> {
         match command {
-            Commands::Open { path, launch } => self.open(path, launch),
-            Commands::Recent {
-                launch,
-                hide_instructions,
-                hide_info,
-            } => self.open_recent(
+            Commands::Open { path, launch } if path == Path::new("-") => {
+                self.open_last(1, None, launch)
+            }
+            Commands::Open { path, launch } if is_history_query(&path) => {
+                self.open_query(path, launch)
+            }
+            Commands::Open { path, launch } => self.open(path, &launch),
+            Commands::Recent { launch, .. } => self.open_recent(launch, None),
+            Commands::Last {
+                nth,
+                filter,
                 launch,
-                PickerOpts {
-                    hide_instructions,
-                    hide_info,
-                },
-            ),
+            } => self.open_last(nth, filter.as_deref(), launch),
             Commands::Config { action } => {
-                let editor = std::env::var("VSCLI_EDITOR").unwrap_or_else(|_| "code".to_string());
+                let editor = self.editor();
                 config_store::run_command(action, &self.config_store, &editor)
             }
             Commands::Container { action } => {
-                let editor = std::env::var("VSCLI_EDITOR").unwrap_or_else(|_| "code".to_string());
-                container::run_command(action, &editor)
+                let editor = self.editor();
+                container::run_command(action, &editor, self.dry_run)
             }
+            Commands::History { action } => history::run_command(
+                action,
+                load_tracker(self.history_path.clone(), self.settings.history_size.value)?,
+                self.dry_run,
+            ),
+            Commands::Editors { all } => {
+                editor::print_editors(&self.editors, &self.editor(), all);
+                Ok(())
+            }
+            Commands::ConfigShow => {
+                self.settings.print();
+                Ok(())
+            }
+        }
+    }
+
+    fn picker_opts(&self) -> PickerOpts {
+        PickerOpts {
+            hide_instructions: self.settings.hide_instructions.value,
+            hide_info: self.settings.hide_info.value,
+            ..PickerOpts::default()
+        }
+    }
+
+    /// Resolves the configured editor, or the first installed editor if none is configured.
+    fn editor(&self) -> editor::Editor {
+        match &self.settings.editor.value {
+            Some(command) => self.editors.resolve(command),
+            None => self.editors.default_editor(),
         }
     }
 
-    fn open(&self, path: PathBuf, launch: LaunchArgs) -> Result<()> {
-        let mut tracker = load_tracker(self.history_path.clone())?;
-        let resolved_config = resolve_launch_config(launch.config.as_ref(), &self.config_store)?;
+    fn open(&self, path: PathBuf, launch: &LaunchArgs) -> Result<()> {
+        let mut tracker =
+            load_tracker(self.history_path.clone(), self.settings.history_size.value)?;
+        let (workspace, fallback, resolved_config, subfolder) = if workspace::is_ssh_uri(&path) {
+            // remote configs can't be resolved locally, so they are used as remote paths
+            let workspace = Workspace::from_ssh_uri(&path.to_string_lossy())?;
+            (workspace, None, launch.config.clone(), None)
+        } else {
+            // a file (optionally with `:line:column`) opens the nearest workspace with the file focused
+            let goto = Goto::parse(&path);
+            let path = match &goto {
+                Some(goto) => {
+                    let parent = goto.file.parent().unwrap_or(&goto.file);
+                    workspace::find_dev_container_root(parent)
+                        .unwrap_or_else(|| parent.to_path_buf())
+                }
+                None => path,
+            };
+            let resolved_config =
+                resolve_launch_config(launch.config.as_ref(), &self.config_store)?;
+            let (workspace_path, subfolder) = match resolved_config.as_ref() {
+                // workspace files are always opened from the folder they live in
+                Some(config) if !workspace::is_workspace_file(&path) => {
+                    workspace_root_from_config(config, &path)?
+                }
+                None if goto.is_none()
+                    && path.is_dir()
+                    && self.settings.strategy.value != ContainerStrategy::ForceClassic =>
+                {
+                    workspace_root_from_ancestors(&path)
+                }
+                _ => (path.clone(), None),
+            };
+            // an ancestor is only the workspace if one of its dev containers is opened
+            let fallback = if resolved_config.is_none() && workspace_path != path {
+                Some(Workspace::from_path(&path)?)
+            } else {
+                None
+            };
+            let mut workspace = Workspace::from_path(&workspace_path)?;
+            workspace.goto = goto;
+            (workspace, fallback, resolved_config, subfolder)
+        };
         let config_name = resolved_config
             .as_ref()
             .and_then(|config| config_store::config_name_from_path(config, &self.config_store));
-        let (workspace_path, subfolder) = if let Some(config) = resolved_config.as_ref() {
-            workspace_root_from_config(config, &path)?
-        } else {
-            (path, None)
-        };
 
-        let workspace = Workspace::from_path(&workspace_path)?;
-        let workspace_name = workspace.name.clone();
+        let opened = |workspace: &Workspace| {
+            (
+                workspace.name.clone(),
+                workspace.path.clone(),
+                workspace.kind,
+            )
+        };
+        let opened_workspace = opened(&workspace);
+        let opened_fallback = fallback.as_ref().map(opened);
+        let remote_host = workspace.host.clone();
+        let remembered = tracker
+            .history
+            .remembered_config(&workspace.path, remote_host.as_deref());
+        // the launch arguments are part of the settings
+        let editor = self.editor();
         let behavior = Behavior {
-            strategy: launch.behavior.unwrap_or_default(),
-            args: launch.args,
-            command: launch.command.unwrap_or_else(|| "code".to_string()),
+            strategy: self.settings.strategy.value,
+            args: self.settings.args.value.clone(),
+            command: editor.command.clone(),
+        };
+        let explicit = Explicit {
+            // an editor of the user settings is not overridden by a repository either
+            editor: self.settings.editor.source.is_users_choice(),
+            args: self.settings.args.source.is_explicit(),
+        };
+        let setup = Setup::new(workspace, behavior.clone(), editor, self.dry_run)
+            .with_launch_mode(launch.launch_mode())
+            .with_customizations(&self.editors, explicit)
+            .with_selector(launch.devcontainer.clone())
+            .with_remembered(remembered, launch.remembered)
+            .with_fallback(fallback);
+        let launched = setup.launch(resolved_config, subfolder.as_deref())?;
+        let (workspace_name, workspace_path, workspace_kind) = match opened_fallback {
+            Some(opened) if launched.fallback => opened,
+            _ => opened_workspace,
         };
-        let setup = Setup::new(workspace, behavior.clone(), self.dry_run);
-        let dev_container = setup.launch(resolved_config, subfolder.as_deref())?;
 
+        let now = Utc::now();
         tracker.history.upsert(Entry {
             workspace_name,
-            dev_container_name: dev_container
+            dev_container_name: launched
+                .dev_container
                 .as_ref()
                 .and_then(|container| container.name.clone()),
             config_name,
-            workspace_path: workspace_path.canonicalize()?,
-            config_path: dev_container.map(|container| container.config_path),
+            workspace_path,
+            workspace_kind,
+            remote_host,
+            config_path: launched
+                .dev_container
+                .map(|container| container.config_path),
+            subfolder: launched.subfolder,
+            container_folder: launched.container_folder,
             behavior,
-            last_opened: Utc::now(),
+            last_opened: now,
+            open_count: 1,
+            recent_opens: vec![now],
+            pinned: false,
         });
         tracker.store()
     }
 
-    fn open_recent(&self, launch: LaunchArgs, picker_opts: PickerOpts) -> Result<()> {
-        let mut tracker = load_tracker(self.history_path.clone())?;
+    fn open_recent(&self, launch: LaunchArgs, query: Option<&str>) -> Result<()> {
+        let mut tracker =
+            load_tracker(self.history_path.clone(), self.settings.history_size.value)?;
+        let picker_opts = self.picker_opts();
         let selected = ui::start(
             &mut tracker,
             picker_opts.hide_instructions,
             picker_opts.hide_info,
+            query,
         )?;
-        let Some((id, mut entry)) = selected else {
+        let Some((id, entry)) = selected else {
             return tracker.store();
         };
+        self.reopen(tracker, id, entry, launch)
+    }
+
+    fn open_last(&self, nth: u16, filter: Option<&str>, launch: LaunchArgs) -> Result<()> {
+        let tracker = load_tracker(self.history_path.clone(), self.settings.history_size.value)?;
+        let (id, entry) = ui::find_last(&tracker.history, nth, filter)?;
+        info!("Reopening {}...", entry.location());
+        self.reopen(tracker, id, entry, launch)
+    }
+
+    /// Opens the history entry matching a path that doesn't exist, like `vscli open my-project`.
+    ///
+    /// A clear best match is opened directly, otherwise the history UI opens with the query.
+    fn open_query(&self, path: PathBuf, launch: LaunchArgs) -> Result<()> {
+        let query = path.to_string_lossy().into_owned();
+        let tracker = load_tracker(self.history_path.clone(), self.settings.history_size.value)?;
+        let mut matches = ui::find_history(&tracker.history, Some(&query));
+        if matches.is_empty() {
+            // report the missing path as usual
+            return self.open(path, &launch);
+        }
+
+        let scores: Vec<u32> = matches.iter().map(|(.., score)| *score).collect();
+        if ui::is_clear_match(&query, &scores) {
+            let (id, entry, _) = matches.swap_remove(0);
+            info!("Reopening {} (matches `{query}`)...", entry.location());
+            return self.reopen(tracker, id, entry, launch);
+        }
+        if !std::io::stdin().is_terminal() || !std::io::stdout().is_terminal() {
+            let choices = matches
+                .iter()
+                .map(|(_, entry, _)| format!("  - {}", entry.location()))
+                .collect::<Vec<_>>()
+                .join("\n");
+            bail!(
+                "`{query}` does not exist and has no clear match among the recent workspaces, but can't ask which one to open without a terminal:\n{choices}"
+            );
+        }
+        self.open_recent(launch, Some(&query))
+    }
 
-        let workspace = Workspace::from_path(&entry.workspace_path)?;
+    /// Opens a history entry again, with its stored behavior and config overridden by `launch`.
+    fn reopen(
+        &self,
+        mut tracker: Tracker,
+        mut id: EntryId,
+        mut entry: Entry,
+        launch: LaunchArgs,
+    ) -> Result<()> {
+        if entry.is_missing() {
+            (id, entry) = relocate(&mut tracker, id, &entry)?;
+        }
+        let workspace = match &entry.remote_host {
+            Some(host) => Workspace::from_remote(host, &entry.workspace_path)?,
+            None => Workspace::from_path(&entry.workspace_path)?,
+        };
         let workspace_name = workspace.name.clone();
+        let workspace_kind = workspace.kind;
+        let launch_mode = launch.launch_mode();
+        let explicit = Explicit {
+            editor: launch.command.is_some(),
+            args: !launch.args.is_empty(),
+        };
         if let Some(command) = launch.command {
             entry.behavior.command = command;
         }
@@ -169,39 +358,126 @@ impl Application {
             entry.behavior.args = launch.args;
         }
 
-        let resolved_config = if launch.config.is_some() {
+        let resolved_config = if entry.remote_host.is_some() && launch.config.is_some() {
+            launch.config.clone()
+        } else if launch.config.is_some() {
             resolve_launch_config(launch.config.as_ref(), &self.config_store)?
+        } else if launch.devcontainer.is_some() {
+            // the dev container is selected again instead of using the remembered config
+            None
         } else {
             entry.config_path.clone()
         };
         let config_name = resolved_config
             .as_ref()
             .and_then(|config| config_store::config_name_from_path(config, &self.config_store));
-        let setup = Setup::new(workspace, entry.behavior.clone(), self.dry_run);
-        let dev_container = setup.launch(resolved_config, None)?;
+        let editor = self.editors.resolve(&entry.behavior.command);
+        let setup = Setup::new(workspace, entry.behavior.clone(), editor, self.dry_run)
+            .with_launch_mode(launch_mode)
+            .with_customizations(&self.editors, explicit)
+            .with_selector(launch.devcontainer);
+        let launched = match setup.launch(resolved_config, entry.subfolder.as_deref()) {
+            Ok(launched) => launched,
+            Err(error) => {
+                // keep changes made in the UI, but don't mark the entry as opened
+                tracker.store()?;
+                return Err(error);
+            }
+        };
 
+        let now = Utc::now();
         tracker.history.update(
             id,
             Entry {
                 workspace_name,
-                dev_container_name: dev_container
+                dev_container_name: launched
+                    .dev_container
                     .as_ref()
                     .and_then(|container| container.name.clone()),
                 config_name,
                 workspace_path: entry.workspace_path,
-                config_path: dev_container.map(|container| container.config_path),
+                workspace_kind,
+                remote_host: entry.remote_host,
+                config_path: launched
+                    .dev_container
+                    .map(|container| container.config_path),
+                subfolder: launched.subfolder,
+                container_folder: launched.container_folder,
                 behavior: entry.behavior,
-                last_opened: Utc::now(),
+                last_opened: now,
+                open_count: 1,
+                recent_opens: vec![now],
+                pinned: entry.pinned,
             },
         );
         tracker.store()
     }
 }
 
+/// Offers to move an entry whose workspace no longer exists to a workspace with the same name and config nearby,
+/// or whose dev container config no longer exists to another config of the workspace.
+fn relocate(tracker: &mut Tracker, id: EntryId, entry: &Entry) -> Result<(EntryId, Entry)> {
+    let moved_config = entry.workspace_path.exists();
+    let (missing_path, candidates, found) = match &entry.config_path {
+        Some(config) if moved_config => (
+            config,
+            history::find_config_relocations(entry),
+            "other dev container configs were found in the workspace",
+        ),
+        _ => (
+            &entry.workspace_path,
+            history::find_relocations(entry),
+            "a workspace with the same name was found",
+        ),
+    };
+    let missing = || {
+        eyre!(
+            "`{}` no longer exists. Remove missing workspaces from the history with `vscli history prune`",
+            missing_path.display()
+        )
+    };
+    if candidates.is_empty() || !ui::is_interactive() {
+        return Err(missing());
+    }
+
+    eprintln!(
+        "`{}` no longer exists, but {found} at:",
+        missing_path.display()
+    );
+    let options: Vec<_> = candidates.iter().map(|path| path.display()).collect();
+    let Some(index) = ui::choose(&options, "Relocate the history entry?")? else {
+        return Err(missing());
+    };
+
+    let relocated = if moved_config {
+        Entry {
+            config_path: Some(candidates[index].clone()),
+            ..entry.clone()
+        }
+    } else {
+        history::relocated(entry, &candidates[index])
+    };
+    info!(
+        "Relocated `{}` to `{}`",
+        missing_path.display(),
+        candidates[index].display()
+    );
+    // the new location may already have its own entry, which takes over the usage and pin of this one
+    match tracker.history.find(&relocated) {
+        Some(existing) => {
+            tracker.history.delete(id);
+            tracker.history.combine(existing, entry);
+            Ok((existing, relocated))
+        }
+        None => Ok((id, relocated)),
+    }
+}
+
 fn main() -> Result<()> {
     color_eyre::install()?;
 
-    let opts = Opts::parse();
+    let matches = Opts::command().get_matches();
+    let opts = Opts::from_arg_matches(&matches).unwrap_or_else(|err| err.exit());
     let opts_dbg = format!("{opts:#?}");
 
     env_logger::Builder::from_default_env()
@@ -211,14 +487,91 @@ fn main() -> Result<()> {
 
     trace!("Parsed Opts:\n{opts_dbg}");
 
+    let mut settings = Settings::load(project_dir(&opts.command).as_deref())?;
+    apply_cli_settings(&mut settings, &opts, &matches);
+    trace!("Settings: {settings:#?}");
+
+    let mut editors = EditorRegistry::default();
+    editors.add_custom(settings.editors.value.clone());
+    editors.set_preference(settings.editor_preference.value.clone());
+
     Application {
         history_path: opts.history_path,
-        config_store: ConfigStore::new(opts.config_dir),
+        config_store: ConfigStore::new(settings.config_dir.value.clone()),
+        editors,
+        settings,
         dry_run: opts.dry_run,
     }
     .run(opts.command)
 }
 
+/// Whether the path argument of `open` is a query against the history, instead of a path.
+fn is_history_query(path: &Path) -> bool {
+    !path.exists() && !workspace::is_ssh_uri(path) && Goto::parse(path).is_none()
+}
+
+/// Returns the directory in which the project settings are looked up.
+fn project_dir(command: &Commands) -> Option<PathBuf> {
+    match command {
+        Commands::Open { path, .. } if workspace::is_ssh_uri(path) => None,
+        Commands::Open { path, .. } => {
+            let dir = if path.is_dir() {
+                path.as_path()
+            } else {
+                path.parent()?
+            };
+            Some(if dir.as_os_str().is_empty() {
+                PathBuf::from(".")
+            } else {
+                dir.to_path_buf()
+            })
+        }
+        _ => std::env::current_dir().ok(),
+    }
+}
+
+/// Applies the settings given as arguments, which take precedence over the settings files.
+fn apply_cli_settings(settings: &mut Settings, opts: &Opts, matches: &ArgMatches) {
+    settings.config_dir.set(
+        opts.config_dir.clone().map(Some),
+        arg_source(matches, "config_dir", "VSCLI_CONFIG_DIR"),
+    );
+    settings.editor_preference.set(
+        Some(opts.editor_preference.clone()).filter(|preference| !preference.is_empty()),
+        arg_source(matches, "editor_preference", "VSCLI_EDITOR_PREFERENCE"),
+    );
+
+    let Some((_, sub_matches)) = matches.subcommand() else {
+        return;
+    };
+    match &opts.command {
+        Commands::Open { launch, .. } => {
+            settings.editor.set(
+                launch.command.clone().map(Some),
+                arg_source(sub_matches, "command", "COMMAND"),
+            );
+            settings.strategy.set(launch.behavior, Source::Cli);
+            settings.args.set(
+                Some(launch.args.clone()).filter(|args| !args.is_empty()),
+                arg_source(sub_matches, "args", "ARGS"),
+            );
+        }
+        Commands::Recent {
+            hide_instructions,
+            hide_info,
+            ..
+        } => {
+            settings
+                .hide_instructions
+                .set(hide_instructions.then_some(true), Source::Cli);
+            settings
+                .hide_info
+                .set(hide_info.then_some(true), Source::Cli);
+        }
+        _ => {}
+    }
+}
+
 /// Formats the log messages in a minimalistic way, since we don't have a lot of output.
 fn log_format(
     buf: &mut env_logger::fmt::Formatter,
@@ -2,6 +2,7 @@ use std::{ffi::OsString, path::PathBuf};
 
 use clap::{Args, Parser, Subcommand};
 
+use crate::editor::LaunchMode;
 use crate::launch::ContainerStrategy;
 
 /// Main CLI arguments
@@ -22,6 +23,15 @@ pub(crate) struct Opts {
     #[arg(long, env = "VSCLI_CONFIG_DIR", global = true)]
     pub config_dir: Option<PathBuf>,
 
+    /// The order in which installed editors are preferred when no editor is specified (comma separated)
+    #[arg(
+        long,
+        env = "VSCLI_EDITOR_PREFERENCE",
+        value_delimiter = ',',
+        global = true
+    )]
+    pub editor_preference: Vec<String>,
+
     /// Whether to launch in dry-run mode (not actually open vscode)
     #[arg(short, long, alias = "dry", env, global = true)]
     pub dry_run: bool,
@@ -38,7 +48,7 @@ pub(crate) struct Opts {
 /// Arguments for launching an editor
 #[derive(Args, Debug, Clone)]
 pub(crate) struct LaunchArgs {
-    /// The editor command to use (e.g. "code", "code-insiders", "cursor")
+    /// The editor to use, as an editor id or command (e.g. "code", "code-insiders", "cursor")
     #[arg(short, long, env)]
     pub command: Option<String>,
 
@@ -50,17 +60,46 @@ pub(crate) struct LaunchArgs {
     #[arg(long, env)]
     pub config: Option<PathBuf>,
 
+    /// Selects the dev container by its name or `.devcontainer/<dir>` folder, instead of asking
+    #[arg(long, conflicts_with = "config")]
+    pub devcontainer: Option<String>,
+
+    /// Opens the dev container used the last time, instead of asking
+    #[arg(long, conflicts_with_all = ["config", "devcontainer"])]
+    pub remembered: bool,
+
+    /// Start the editor in the background, without checking whether it launched successfully
+    #[arg(long, conflicts_with = "wait")]
+    pub detach: bool,
+
+    /// Wait until the editor window is closed
+    #[arg(long)]
+    pub wait: bool,
+
     /// Additional arguments to pass to the editor
     #[arg(value_parser, env)]
     pub args: Vec<OsString>,
 }
 
+impl LaunchArgs {
+    /// Returns how vscli should wait for the launched editor.
+    pub fn launch_mode(&self) -> LaunchMode {
+        if self.detach {
+            LaunchMode::Detach
+        } else if self.wait {
+            LaunchMode::Wait
+        } else {
+            LaunchMode::Check
+        }
+    }
+}
+
 #[derive(Subcommand, Debug)]
 pub(crate) enum Commands {
     /// Opens a dev container.
     #[clap(alias = "o")]
     Open {
-        /// The path of the vscode project to open
+        /// The path of the vscode project to open (`-` reopens the last workspace)
         #[arg(value_parser, default_value = ".")]
         path: PathBuf,
 
@@ -81,6 +120,19 @@ pub(crate) enum Commands {
         #[command(flatten)]
         launch: LaunchArgs,
     },
+    /// Reopens the last opened workspace, without the interactive list.
+    Last {
+        /// Reopen the n-th most recent workspace instead (1 is the most recent one)
+        #[arg(short, long, default_value_t = 1, value_parser = clap::value_parser!(u16).range(1..))]
+        nth: u16,
+
+        /// Reopen the best match of a fuzzy query (like in the `recent` UI) instead
+        #[arg(short, long)]
+        filter: Option<String>,
+
+        #[command(flatten)]
+        launch: LaunchArgs,
+    },
     /// Manage external devcontainer configurations.
     #[clap(alias = "cfg")]
     Config {
@@ -93,6 +145,20 @@ pub(crate) enum Commands {
         #[command(subcommand)]
         action: ContainerAction,
     },
+    /// Manage the history of recently used workspaces.
+    #[clap(alias = "hist")]
+    History {
+        #[command(subcommand)]
+        action: HistoryAction,
+    },
+    /// Lists the editors found in PATH.
+    Editors {
+        /// Include known editors that are not installed.
+        #[arg(short, long)]
+        all: bool,
+    },
+    /// Shows the effective settings and where each value comes from.
+    ConfigShow,
 }
 
 #[derive(Subcommand, Debug)]
@@ -129,6 +195,104 @@ pub(crate) enum ConfigAction {
     },
 }
 
+/// Filters for history entries (all given filters have to match)
+#[derive(Args, Debug, Clone, Default)]
+pub(crate) struct HistoryFilter {
+    /// Only entries whose workspace name contains this text (case insensitive)
+    #[arg(long)]
+    pub name: Option<String>,
+
+    /// Only entries whose external config or dev container name contains this text (case insensitive)
+    #[arg(long)]
+    pub config: Option<String>,
+
+    /// Only entries opened with this editor command
+    #[arg(long)]
+    pub editor: Option<String>,
+
+    /// Only entries with this launch behavior
+    #[arg(long, ignore_case = true)]
+    pub strategy: Option<ContainerStrategy>,
+
+    /// Only pinned entries
+    #[arg(long)]
+    pub pinned: bool,
+
+    /// Only entries whose workspace or dev container config no longer exists
+    #[arg(long)]
+    pub missing: bool,
+}
+
+#[derive(Subcommand, Debug)]
+pub(crate) enum HistoryAction {
+    /// List the history entries, in the order of the recent UI.
+    #[clap(alias = "ls")]
+    List {
+        #[command(flatten)]
+        filter: HistoryFilter,
+
+        /// Print the entries as JSON.
+        #[arg(long)]
+        json: bool,
+    },
+    /// Show the details of history entries.
+    Show {
+        /// Index (from `history list`) or workspace path of the entries.
+        target: String,
+
+        /// Print the entries as JSON.
+        #[arg(long)]
+        json: bool,
+    },
+    /// Remove history entries.
+    Rm {
+        /// Indices (from `history list`) or workspace paths of the entries.
+        #[arg(required = true)]
+        targets: Vec<String>,
+    },
+    /// Pin history entries, so they are listed first and never removed when the history is full.
+    Pin {
+        /// Indices (from `history list`) or workspace paths of the entries.
+        #[arg(required = true)]
+        targets: Vec<String>,
+    },
+    /// Unpin history entries.
+    Unpin {
+        /// Indices (from `history list`) or workspace paths of the entries.
+        #[arg(required = true)]
+        targets: Vec<String>,
+    },
+    /// Remove the history entries matching the filters (by default the missing ones).
+    Prune {
+        #[command(flatten)]
+        filter: HistoryFilter,
+
+        /// Only entries that were not opened for this number of days
+        #[arg(long)]
+        older_than: Option<u32>,
+    },
+    /// Remove all history entries.
+    Clear {
+        /// Do not ask for confirmation.
+        #[arg(short, long)]
+        yes: bool,
+    },
+    /// Write the history entries as JSON to a file or stdout.
+    Export {
+        /// The file to write (default: stdout).
+        path: Option<PathBuf>,
+    },
+    /// Add the history entries of an exported file (`-` reads stdin).
+    Import {
+        /// The file to read.
+        path: PathBuf,
+
+        /// Replace the history instead of merging the entries into it.
+        #[arg(long)]
+        replace: bool,
+    },
+}
+
 #[derive(Subcommand, Debug)]
 pub(crate) enum ContainerAction {
     /// Open interactive container picker.
@@ -145,6 +309,11 @@ pub(crate) enum ContainerAction {
         /// Container ID or ID prefix.
         id: String,
     },
+    /// Attach the editor to a running container.
+    Attach {
+        /// Container ID, ID prefix or name.
+        id: String,
+    },
     /// Stop a running devcontainer.
     Stop {
         /// Container ID or ID prefix.
@@ -0,0 +1,438 @@
+use clap::ArgMatches;
+use clap::parser::ValueSource;
+use color_eyre::eyre::{Result, WrapErr};
+use log::{debug, warn};
+use serde::{Deserialize, Deserializer};
+use std::ffi::OsString;
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+
+use crate::editor::{Editor, EditorRegistry};
+use crate::history::MAX_HISTORY_ENTRIES;
+use crate::launch::ContainerStrategy;
+
+/// The name of the per-project settings file.
+pub const PROJECT_SETTINGS_FILE: &str = ".vscli.toml";
+
+/// Where the value of a setting comes from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    /// The default of vscli.
+    BuiltIn,
+    /// The user settings file.
+    User(PathBuf),
+    /// The project settings file.
+    Project(PathBuf),
+    /// An environment variable.
+    Env(String),
+    /// A command line argument.
+    Cli,
+}
+
+impl Source {
+    /// Whether the value was given for this invocation (on the command line or in the environment).
+    pub fn is_explicit(&self) -> bool {
+        matches!(self, Self::Cli | Self::Env(_))
+    }
+
+    /// Whether the value was chosen by the user instead of a project (explicitly or in the user settings).
+    pub fn is_users_choice(&self) -> bool {
+        self.is_explicit() || matches!(self, Self::User(_))
+    }
+}
+
+impl Display for Source {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::BuiltIn => f.write_str("built-in"),
+            Self::User(path) => write!(f, "user: {}", path.display()),
+            Self::Project(path) => write!(f, "project: {}", path.display()),
+            Self::Env(name) => write!(f, "env: {name}"),
+            Self::Cli => f.write_str("command line"),
+        }
+    }
+}
+
+/// A setting value together with its source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Setting<T> {
+    pub value: T,
+    pub source: Source,
+}
+
+impl<T> Setting<T> {
+    fn builtin(value: T) -> Self {
+        Self {
+            value,
+            source: Source::BuiltIn,
+        }
+    }
+
+    /// Overrides the value, if one is given.
+    pub fn set(&mut self, value: Option<T>, source: Source) {
+        if let Some(value) = value {
+            self.value = value;
+            self.source = source;
+        }
+    }
+}
+
+/// The content of a settings file (`config.toml` or `.vscli.toml`).
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct SettingsFile {
+    editor: Option<String>,
+    editor_preference: Option<Vec<String>>,
+    #[serde(default, deserialize_with = "deserialize_strategy")]
+    strategy: Option<ContainerStrategy>,
+    args: Option<Vec<String>>,
+    history_size: Option<usize>,
+    hide_instructions: Option<bool>,
+    hide_info: Option<bool>,
+    config_dir: Option<PathBuf>,
+    #[serde(default)]
+    editors: Vec<Editor>,
+}
+
+/// Parses the strategy like on the command line (e.g. `force-container`).
+fn deserialize_strategy<'de, D>(deserializer: D) -> Result<Option<ContainerStrategy>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Option::<String>::deserialize(deserializer)?
+        .map(|strategy| strategy.parse().map_err(serde::de::Error::custom))
+        .transpose()
+}
+
+/// The effective settings, merged in the order: CLI > env > project > user > built-in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Settings {
+    /// The editor used when none is given, `None` uses the first installed editor.
+    pub editor: Setting<Option<String>>,
+    /// The order in which installed editors are preferred.
+    pub editor_preference: Setting<Vec<String>>,
+    /// The dev container strategy of new workspaces.
+    pub strategy: Setting<ContainerStrategy>,
+    /// Additional arguments passed to the editor.
+    pub args: Setting<Vec<OsString>>,
+    /// The maximum number of history entries.
+    pub history_size: Setting<usize>,
+    /// Whether the pickers hide the keybinding instructions.
+    pub hide_instructions: Setting<bool>,
+    /// Whether the pickers hide the additional information.
+    pub hide_info: Setting<bool>,
+    /// The config store directory, `None` uses the default location.
+    pub config_dir: Setting<Option<PathBuf>>,
+    /// The custom editors of the user settings file.
+    pub editors: Setting<Vec<Editor>>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            editor: Setting::builtin(None),
+            editor_preference: Setting::builtin(Vec::new()),
+            strategy: Setting::builtin(ContainerStrategy::default()),
+            args: Setting::builtin(Vec::new()),
+            history_size: Setting::builtin(MAX_HISTORY_ENTRIES),
+            hide_instructions: Setting::builtin(false),
+            hide_info: Setting::builtin(false),
+            config_dir: Setting::builtin(None),
+            editors: Setting::builtin(Vec::new()),
+        }
+    }
+}
+
+impl Settings {
+    /// Loads the user settings and the project settings of `project_dir`, and applies the environment.
+    ///
+    /// User settings path: `$XDG_CONFIG_HOME/vscli/config.toml` (typically `~/.config/vscli/config.toml`).
+    /// The project settings are looked up in `project_dir` and its parent directories.
+    pub fn load(project_dir: Option<&Path>) -> Result<Self> {
+        let user = dirs::config_dir().map(|mut path| {
+            path.push("vscli");
+            path.push("config.toml");
+            path
+        });
+        let project = project_dir.and_then(find_project_settings);
+        Self::load_from(user.as_deref(), project.as_deref())
+    }
+
+    fn load_from(user: Option<&Path>, project: Option<&Path>) -> Result<Self> {
+        let mut settings = Self::default();
+        if let Some(path) = user.filter(|path| path.is_file()) {
+            settings.apply_file(path, &Source::User(path.to_path_buf()))?;
+        }
+        if let Some(path) = project.filter(|path| path.is_file()) {
+            settings.apply_file(path, &Source::Project(path.to_path_buf()))?;
+        }
+        settings.apply_env();
+        Ok(settings)
+    }
+
+    fn apply_file(&mut self, path: &Path, source: &Source) -> Result<()> {
+        let content = std::fs::read_to_string(path)
+            .wrap_err_with(|| format!("Failed to read settings file: {}", path.display()))?;
+        let file: SettingsFile = toml::from_str(&content)
+            .wrap_err_with(|| format!("Failed to parse settings file: {}", path.display()))?;
+        debug!("Loaded settings file: {}", path.display());
+
+        // relative directories are resolved relative to the settings file
+        let base = path.parent().unwrap_or(Path::new("."));
+        self.strategy.set(file.strategy, source.clone());
+        self.hide_instructions
+            .set(file.hide_instructions, source.clone());
+        self.hide_info.set(file.hide_info, source.clone());
+        self.config_dir.set(
+            file.config_dir.map(|dir| Some(base.join(dir))),
+            source.clone(),
+        );
+        if let Source::Project(_) = source {
+            self.apply_project_editor(path, file.editor, source);
+            // these would let a project run other programs or change the global history
+            let user_only = [
+                ("editor-preference", file.editor_preference.is_some()),
+                ("args", file.args.is_some()),
+                ("history-size", file.history_size.is_some()),
+                ("editors", !file.editors.is_empty()),
+            ];
+            for (key, _) in user_only.iter().filter(|(_, is_set)| *is_set) {
+                warn!(
+                    "Ignoring `{key}` of the project settings file {}, it can only be set in the user settings",
+                    path.display()
+                );
+            }
+            return Ok(());
+        }
+
+        self.editor.set(file.editor.map(Some), source.clone());
+        self.editor_preference
+            .set(file.editor_preference, source.clone());
+        self.args.set(
+            file.args
+                .map(|args| args.into_iter().map(OsString::from).collect()),
+            source.clone(),
+        );
+        self.history_size.set(file.history_size, source.clone());
+        if !file.editors.is_empty() {
+            self.editors.set(Some(file.editors), source.clone());
+        }
+        Ok(())
+    }
+
+    /// Applies the editor of a project settings file, if it is the id of a built-in editor
+    /// or a custom editor of the user.
+    fn apply_project_editor(&mut self, path: &Path, editor: Option<String>, source: &Source) {
+        let Some(editor) = editor else {
+            return;
+        };
+        let known = EditorRegistry::default().resolve_id(&editor).is_some()
+            || self
+                .editors
+                .value
+                .iter()
+                .any(|custom| custom.id.eq_ignore_ascii_case(&editor));
+        if known {
+            self.editor.set(Some(Some(editor)), source.clone());
+        } else {
+            warn!(
+                "Ignoring the editor `{editor}` of the project settings file {}, it is not the id of a known editor",
+                path.display()
+            );
+        }
+    }
+
+    /// Applies the environment variables which are not handled by the command line parser.
+    fn apply_env(&mut self) {
+        if let Ok(editor) = std::env::var("VSCLI_EDITOR") {
+            self.editor
+                .set(Some(Some(editor)), Source::Env("VSCLI_EDITOR".to_string()));
+        }
+        if let Ok(size) = std::env::var("VSCLI_HISTORY_SIZE") {
+            match size.parse() {
+                Ok(size) => self
+                    .history_size
+                    .set(Some(size), Source::Env("VSCLI_HISTORY_SIZE".to_string())),
+                Err(err) => warn!("Ignoring invalid VSCLI_HISTORY_SIZE `{size}`: {err}"),
+            }
+        }
+    }
+
+    /// Prints the effective settings and their sources.
+    pub fn print(&self) {
+        let rows = [
+            (
+                "editor",
+                self.editor
+                    .value
+                    .as_deref()
+                    .map_or_else(|| "(first installed)".to_string(), quote),
+                &self.editor.source,
+            ),
+            (
+                "editor-preference",
+                list(self.editor_preference.value.iter().map(String::as_str)),
+                &self.editor_preference.source,
+            ),
+            (
+                "strategy",
+                quote(&self.strategy.value.to_string()),
+                &self.strategy.source,
+            ),
+            (
+                "args",
+                list(
+                    self.args
+                        .value
+                        .iter()
+                        .map(|arg| arg.to_str().unwrap_or("?")),
+                ),
+                &self.args.source,
+            ),
+            (
+                "history-size",
+                self.history_size.value.to_string(),
+                &self.history_size.source,
+            ),
+            (
+                "hide-instructions",
+                self.hide_instructions.value.to_string(),
+                &self.hide_instructions.source,
+            ),
+            (
+                "hide-info",
+                self.hide_info.value.to_string(),
+                &self.hide_info.source,
+            ),
+            (
+                "config-dir",
+                self.config_dir.value.as_deref().map_or_else(
+                    || "(default)".to_string(),
+                    |dir| quote(&dir.display().to_string()),
+                ),
+                &self.config_dir.source,
+            ),
+            (
+                "editors",
+                list(self.editors.value.iter().map(|editor| editor.id.as_str())),
+                &self.editors.source,
+            ),
+        ];
+
+        let value_width = rows
+            .iter()
+            .map(|(_, value, _)| value.len())
+            .max()
+            .unwrap_or(0);
+        for (key, value, source) in rows {
+            println!("{key:<17} = {value:<value_width$}  # {source}");
+        }
+    }
+}
+
+/// Returns whether an argument was set on the command line or through its environment variable.
+pub fn arg_source(matches: &ArgMatches, id: &str, env: &str) -> Source {
+    if matches.value_source(id) == Some(ValueSource::EnvVariable) {
+        Source::Env(env.to_string())
+    } else {
+        Source::Cli
+    }
+}
+
+/// Finds the nearest project settings file, starting with `start` itself.
+fn find_project_settings(start: &Path) -> Option<PathBuf> {
+    let start = std::fs::canonicalize(start).ok()?;
+    start
+        .ancestors()
+        .map(|dir| dir.join(PROJECT_SETTINGS_FILE))
+        .find(|path| path.is_file())
+}
+
+fn quote(value: &str) -> String {
+    format!("{value:?}")
+}
+
+fn list<'a>(values: impl Iterator<Item = &'a str>) -> String {
+    format!("[{}]", values.map(quote).collect::<Vec<_>>().join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_settings_files() {
+        let temp = tempfile::tempdir().unwrap();
+        let user = temp.path().join("config.toml");
+        std::fs::write(
+            &user,
+            r#"
+            editor = "cursor"
+            strategy = "force-classic"
+            history-size = 10
+            config-dir = "configs"
+
+            [[editors]]
+            id = "my-fork"
+            name = "My Fork"
+            binaries = ["my-fork"]
+            "#,
+        )
+        .unwrap();
+        let project_dir = temp.path().join("project/sub");
+        std::fs::create_dir_all(&project_dir).unwrap();
+        let project = temp.path().join("project").join(PROJECT_SETTINGS_FILE);
+        std::fs::write(
+            &project,
+            r#"
+            editor = "windsurf"
+            strategy = "force-container"
+            args = ["-c", "echo hi"]
+            history-size = 0
+
+            [[editors]]
+            id = "team-editor"
+            name = "Team Editor"
+            binaries = ["team-editor"]
+            dev-containers = false
+            "#,
+        )
+        .unwrap();
+
+        let found = find_project_settings(&project_dir).unwrap();
+        assert_eq!(found, project.canonicalize().unwrap());
+
+        let settings = Settings::load_from(Some(&user), Some(&project)).unwrap();
+        assert_eq!(settings.strategy.value, ContainerStrategy::ForceContainer);
+        assert_eq!(settings.strategy.source, Source::Project(project.clone()));
+        assert_eq!(settings.editor.value.as_deref(), Some("windsurf"));
+        assert_eq!(settings.editor.source, Source::Project(project.clone()));
+        // options that could run other programs or change the global history are ignored
+        assert!(settings.args.value.is_empty());
+        assert_eq!(settings.history_size.value, 10);
+        assert_eq!(settings.history_size.source, Source::User(user.clone()));
+        assert_eq!(settings.config_dir.value, Some(temp.path().join("configs")));
+        assert_eq!(settings.hide_info.source, Source::BuiltIn);
+        // custom editors of the project are ignored
+        let editors: Vec<&str> = settings
+            .editors
+            .value
+            .iter()
+            .map(|editor| editor.id.as_str())
+            .collect();
+        assert_eq!(editors, ["my-fork"]);
+        assert_eq!(settings.editors.source, Source::User(user.clone()));
+
+        // only known editors can be set by a project
+        std::fs::write(&project, "editor = \"sh\"\n").unwrap();
+        let settings = Settings::load_from(Some(&user), Some(&project)).unwrap();
+        assert_eq!(settings.editor.value.as_deref(), Some("cursor"));
+        assert_eq!(settings.editor.source, Source::User(user.clone()));
+        std::fs::write(&project, "editor = \"my-fork\"\n").unwrap();
+        let settings = Settings::load_from(Some(&user), Some(&project)).unwrap();
+        assert_eq!(settings.editor.value.as_deref(), Some("my-fork"));
+
+        std::fs::write(&project, "unknown = true\n").unwrap();
+        assert!(Settings::load_from(Some(&user), Some(&project)).is_err());
+    }
+}
@@ -1,5 +1,5 @@
 // allow: SIZE_OK - The generic picker keeps event transitions and render state co-located.
-use color_eyre::eyre::Result;
+use color_eyre::eyre::{Result, bail};
 use crossterm::{
     event::{
         self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind, KeyModifiers,
@@ -27,13 +27,16 @@ use ratatui::{
 };
 use ratatui_textarea::TextArea;
 use std::io;
+use std::path::Path;
 
 use crate::history::{Entry, EntryId, History, Tracker};
 
 mod items;
+mod prompt;
 
 pub use items::{ConfigItem, ContainerItem};
 use items::{DevContainerItem, HistoryItem};
+pub use prompt::{choose, confirm, is_interactive};
 
 /// Describes an item that can be rendered and filtered by the generic picker UI.
 pub trait Pickable: Clone {
@@ -54,6 +57,11 @@ pub trait Pickable: Clone {
 
     /// Table column constraints based on computed maximum cell widths.
     fn column_constraints(max_widths: &[usize]) -> Vec<Constraint>;
+
+    /// Style of the table row.
+    fn style(&self) -> Style {
+        Style::default()
+    }
 }
 
 trait WrappedPickable: Pickable {
@@ -71,6 +79,17 @@ pub struct PickerOpts {
     pub hide_instructions: bool,
     /// Hide additional status/info lines.
     pub hide_info: bool,
+    /// Enable the attach keybinding.
+    pub allow_attach: bool,
+}
+
+/// Describes how the picked item should be used.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PickerAction {
+    /// The item was opened (e.g. with `Enter`).
+    Open,
+    /// The item should be attached to (e.g. with `Ctrl+A`).
+    Attach,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -81,6 +100,8 @@ enum AppAction {
     SelectFirst,
     SelectLast,
     OpenSelected,
+    AttachSelected,
+    TogglePinSelected,
     DeleteSelectedEntry,
     SearchInput(ratatui_textarea::Input),
     TableClick(u16),
@@ -123,8 +144,8 @@ impl<T: Pickable> PickerData<T> {
             }
 
             rows.push(PickerRow {
+                row: Row::new(cells).style(item.style()).height(1),
                 item,
-                row: Row::new(cells).height(1),
                 search_score: Some(0),
                 original_index: index,
             });
@@ -206,6 +227,8 @@ struct PickerState<'a, T: Pickable> {
     table_state: TableState,
     table_data: PickerData<T>,
     opts: PickerOpts,
+    /// Whether the pin keybinding is enabled (with a pin callback).
+    allow_pin: bool,
     last_clicked_index: Option<usize>,
 }
 
@@ -216,10 +239,17 @@ impl<T: Pickable> PickerState<'_, T> {
             table_state: TableState::default(),
             table_data: PickerData::from_items(items),
             opts,
+            allow_pin: false,
             last_clicked_index: None,
         }
     }
 
+    /// Enters a search query, as if it was typed.
+    fn set_query(&mut self, query: &str) {
+        self.search.insert_str(query);
+        self.apply_filter(Some(query));
+    }
+
     fn select_relative(&mut self, direction: SelectionDirection) {
         let len = self.table_data.as_rows_full().count();
         if len == 0 {
@@ -283,6 +313,24 @@ impl<T: Pickable> PickerState<'_, T> {
         self.table_data.as_rows_full().nth(index).cloned()
     }
 
+    /// Changes the selected item and updates its row.
+    fn update_selected(&mut self, update: impl FnOnce(&mut T)) {
+        let Some(index) = self.table_state.selected() else {
+            return;
+        };
+        let Some(row) = self
+            .table_data
+            .rows
+            .iter_mut()
+            .filter(|row| row.search_score.is_some())
+            .nth(index)
+        else {
+            return;
+        };
+        update(&mut row.item);
+        row.row = Row::new(row.item.cells()).style(row.item.style()).height(1);
+    }
+
     fn delete(&mut self, original_index: usize) -> bool {
         self.table_data.delete_by_original_index(original_index)
     }
@@ -318,6 +366,27 @@ pub fn pick<T: Pickable>(
     opts: PickerOpts,
     on_delete: Option<&mut dyn FnMut(&T)>,
 ) -> Result<Option<T>> {
+    Ok(pick_with_action(items, opts, on_delete)?.map(|(item, _)| item))
+}
+
+/// Starts a generic picker and returns the selected item together with the action used to pick it.
+///
+/// # Errors
+///
+/// Returns an error if terminal setup/teardown fails or if input/rendering fails.
+pub fn pick_with_action<T: Pickable>(
+    items: Vec<T>,
+    opts: PickerOpts,
+    on_delete: Option<&mut dyn FnMut(&T)>,
+) -> Result<Option<(T, PickerAction)>> {
+    run_picker(PickerState::new(items, opts), on_delete, None)
+}
+
+fn run_picker<T: Pickable>(
+    app: PickerState<'_, T>,
+    on_delete: Option<&mut dyn FnMut(&T)>,
+    on_pin: Option<&mut dyn FnMut(&mut T)>,
+) -> Result<Option<(T, PickerAction)>> {
     debug!("Starting UI...");
 
     debug!("Entering raw mode & alternate screen...");
@@ -328,7 +397,7 @@ pub fn pick<T: Pickable>(
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    let res = run_app(&mut terminal, PickerState::new(items, opts), on_delete);
+    let res = run_app(&mut terminal, app, on_delete, on_pin);
 
     disable_raw_mode()?;
     execute!(
@@ -344,6 +413,8 @@ pub fn pick<T: Pickable>(
 
 /// Starts the history UI and returns the selected history entry.
 ///
+/// The list is filtered by `query` initially, if given.
+///
 /// # Errors
 ///
 /// Returns an error if terminal setup/teardown fails or if input/rendering fails.
@@ -351,47 +422,126 @@ pub fn start(
     tracker: &mut Tracker,
     hide_instructions: bool,
     hide_info: bool,
+    query: Option<&str>,
 ) -> Result<Option<(EntryId, Entry)>> {
-    let items = sorted_history_items(&tracker.history);
+    let items = ranked_items(&tracker.history);
     let opts = PickerOpts {
         hide_instructions,
         hide_info,
+        ..PickerOpts::default()
     };
 
     let mut on_delete = |item: &HistoryItem| {
         let _ = tracker.history.delete(item.id);
     };
+    // the pins are applied to the history after the picker closed
+    let mut pins = Vec::new();
+    let mut on_pin = |item: &mut HistoryItem| {
+        item.entry.pinned = !item.entry.pinned;
+        pins.push((item.id, item.entry.pinned));
+    };
 
-    let selected = pick(items, opts, Some(&mut on_delete))?;
-    Ok(selected.map(|item| (item.id, item.entry)))
+    let mut app = PickerState::new(items, opts);
+    if let Some(query) = query {
+        app.set_query(query);
+    }
+    let selected = run_picker(app, Some(&mut on_delete), Some(&mut on_pin))?;
+    for (id, pinned) in pins {
+        // deleted entries are ignored
+        let _ = tracker.history.set_pinned(id, pinned);
+    }
+    Ok(selected.map(|(item, _)| (item.id, item.entry)))
 }
 
-fn sorted_history_items(history: &History) -> Vec<HistoryItem> {
-    let mut items: Vec<HistoryItem> = history
-        .iter()
-        .map(|(id, entry)| HistoryItem {
-            id: *id,
-            entry: entry.clone(),
-        })
-        .collect();
+/// Returns the history entries matching `filter` together with their search score,
+/// ordered by the same fuzzy matching and ranking as the history UI.
+///
+/// Without a filter, the entries are returned from the most recent to the oldest one instead,
+/// as `vscli last` goes strictly by the time of the last open.
+pub fn find_history(history: &History, filter: Option<&str>) -> Vec<(EntryId, Entry, u32)> {
+    let filter = filter.filter(|filter| !filter.trim().is_empty());
+    let mut items = ranked_items(history);
+    if filter.is_none() {
+        items.sort_by_key(|item| std::cmp::Reverse(item.entry.last_opened));
+    }
+    let mut data = PickerData::from_items(items);
+    if let Some(filter) = filter {
+        data.apply_filter(filter);
+    }
+    data.rows
+        .into_iter()
+        .filter_map(|row| Some((row.item.id, row.item.entry, row.search_score?)))
+        .collect()
+}
+
+/// How much better the best history match of a query has to score than the second one to be a clear match.
+const CLEAR_MATCH_RATIO: u32 = 2;
 
-    items.sort_by_key(|item| -item.entry.last_opened.timestamp());
-    items
+/// The search score per character of the query that a clear match needs at least.
+///
+/// Every matched character scores 16 in each field, less with gaps in between.
+/// As the workspace name is part of the path as well, a query closely matching the name scores about twice that.
+const CLEAR_MATCH_SCORE_PER_CHAR: u32 = 32;
+
+/// Whether the best history match of `query` is clearly the one meant, given the `scores` of all matches
+/// as returned by [`find_history`].
+///
+/// The best match has to score well for the length of the query, even when it is the only one,
+/// and clearly better than the second one.
+pub fn is_clear_match(query: &str, scores: &[u32]) -> bool {
+    let query_chars = query.chars().filter(|c| !c.is_whitespace()).count();
+    let min_score = u32::try_from(query_chars)
+        .unwrap_or(u32::MAX)
+        .saturating_mul(CLEAR_MATCH_SCORE_PER_CHAR);
+    match scores {
+        [] => false,
+        [best] => *best >= min_score,
+        [best, second, ..] => {
+            *best >= min_score && *best >= second.saturating_mul(CLEAR_MATCH_RATIO)
+        }
+    }
+}
+
+/// Returns the `nth` most recent history entry, counted from 1, among the ones matching `filter`.
+pub fn find_last(history: &History, nth: u16, filter: Option<&str>) -> Result<(EntryId, Entry)> {
+    let mut entries = find_history(history, filter).into_iter();
+    let Some((id, entry, _)) = usize::from(nth)
+        .checked_sub(1)
+        .and_then(|index| entries.nth(index))
+    else {
+        match filter {
+            Some(filter) => bail!("No history entry #{nth} matches `{filter}`."),
+            None => bail!("No history entry #{nth} found."),
+        }
+    };
+    Ok((id, entry))
+}
+
+/// Returns the history items, the most frequently and recently used first
+fn ranked_items(history: &History) -> Vec<HistoryItem> {
+    history
+        .ranked()
+        .into_iter()
+        .map(|(id, entry)| HistoryItem::new(id, entry.clone()))
+        .collect()
 }
 
 fn run_app<T: Pickable>(
     terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
     mut app: PickerState<'_, T>,
     on_delete: Option<&mut dyn FnMut(&T)>,
-) -> io::Result<Option<T>> {
+    on_pin: Option<&mut dyn FnMut(&mut T)>,
+) -> io::Result<Option<(T, PickerAction)>> {
     app.table_state.select(Some(0));
     let mut on_delete = on_delete;
+    let mut on_pin = on_pin;
+    app.allow_pin = on_pin.is_some();
 
     loop {
         terminal.draw(|f| render(f, &mut app))?;
 
         let input = event::read()?;
-        let action = handle_input(&input);
+        let action = handle_input(&input, app.opts, app.allow_pin);
 
         if let Some(action) = action {
             match action {
@@ -414,7 +564,17 @@ fn run_app<T: Pickable>(
                 }
                 AppAction::OpenSelected => {
                     if let Some(selected) = app.get_selected_row() {
-                        return Ok(Some(selected.item));
+                        return Ok(Some((selected.item, PickerAction::Open)));
+                    }
+                }
+                AppAction::AttachSelected => {
+                    if let Some(selected) = app.get_selected_row() {
+                        return Ok(Some((selected.item, PickerAction::Attach)));
+                    }
+                }
+                AppAction::TogglePinSelected => {
+                    if let Some(callback) = on_pin.as_deref_mut() {
+                        app.update_selected(callback);
                     }
                 }
                 AppAction::DeleteSelectedEntry => {
@@ -428,7 +588,7 @@ fn run_app<T: Pickable>(
                 }
                 AppAction::TableClick(row) => {
                     if let Some(selected) = app.select_clicked(row, terminal.get_frame().area()) {
-                        return Ok(Some(selected));
+                        return Ok(Some((selected, PickerAction::Open)));
                     }
                 }
                 AppAction::SearchInput(input) => {
@@ -443,7 +603,7 @@ fn run_app<T: Pickable>(
     }
 }
 
-fn handle_input(input: &Event) -> Option<AppAction> {
+fn handle_input(input: &Event, opts: PickerOpts, allow_pin: bool) -> Option<AppAction> {
     match input {
         Event::Key(key) => {
             if key.kind != KeyEventKind::Press {
@@ -467,6 +627,10 @@ fn handle_input(input: &Event) -> Option<AppAction> {
                 return Some(AppAction::SelectLast);
             } else if is_key(KeyCode::Enter) || is_ctrl_char('o') {
                 return Some(AppAction::OpenSelected);
+            } else if opts.allow_attach && is_ctrl_char('a') {
+                return Some(AppAction::AttachSelected);
+            } else if allow_pin && is_ctrl_char('p') {
+                return Some(AppAction::TogglePinSelected);
             } else if is_key(KeyCode::Delete) || is_ctrl_char('r') || is_ctrl_char('x') {
                 return Some(AppAction::DeleteSelectedEntry);
             }
@@ -517,13 +681,7 @@ fn render<T: Pickable>(frame: &mut Frame, app: &mut PickerState<'_, T>) {
 
     render_table(frame, app, area[0]);
     render_search_input(frame, app, area[1]);
-    render_status_area(
-        frame,
-        &status_lines,
-        &area[2..],
-        app.opts.hide_instructions,
-        app.opts.hide_info,
-    );
+    render_status_area(frame, &status_lines, &area[2..], app.opts, app.allow_pin);
 }
 
 fn render_search_input<T: Pickable>(frame: &mut Frame, app: &mut PickerState<'_, T>, area: Rect) {
@@ -589,12 +747,24 @@ fn render_status_area(
     frame: &mut Frame,
     status_lines: &[String],
     areas: &[Rect],
-    hide_instructions: bool,
-    hide_info: bool,
+    opts: PickerOpts,
+    allow_pin: bool,
 ) {
-    if !hide_instructions {
+    if !opts.hide_instructions {
         let instruction = Span::styled(
-            "↑/↓ to navigate • Del/Ctrl+X to remove • Enter to open • Type to filter • Esc/Ctrl+C to quit",
+            format!(
+                "↑/↓ to navigate • Del/Ctrl+X to remove • Enter to open • {}{}Type to filter • Esc/Ctrl+C to quit",
+                if opts.allow_attach {
+                    "Ctrl+A to attach • "
+                } else {
+                    ""
+                },
+                if allow_pin {
+                    "Ctrl+P to pin/unpin • "
+                } else {
+                    ""
+                },
+            ),
             Style::default().fg(Color::Gray),
         );
         let instructions_par = Paragraph::new(instruction)
@@ -603,7 +773,7 @@ fn render_status_area(
         frame.render_widget(instructions_par, areas[0]);
     }
 
-    if !hide_info && areas.len() > 1 {
+    if !opts.hide_info && areas.len() > 1 {
         for (index, line) in status_lines.iter().enumerate() {
             if let Some(area) = areas.get(index + 1) {
                 let info = Span::styled(line.clone(), Style::default().fg(Color::DarkGray));
@@ -617,12 +787,19 @@ fn render_status_area(
 }
 
 /// Launches a picker for Docker devcontainers.
+///
+/// Returns the selected container and whether it should be reopened or attached to.
 pub fn pick_container(
     containers: Vec<crate::container::Container>,
     opts: PickerOpts,
     on_delete: Option<&mut dyn FnMut(&ContainerItem)>,
-) -> Result<Option<crate::container::Container>> {
-    pick_wrapped::<ContainerItem>(containers, opts, on_delete)
+) -> Result<Option<(crate::container::Container, PickerAction)>> {
+    let items = containers
+        .into_iter()
+        .map(ContainerItem::from_inner)
+        .collect();
+    let selected = pick_with_action(items, opts, on_delete)?;
+    Ok(selected.map(|(item, action)| (item.into_inner(), action)))
 }
 
 /// Launches a picker for stored devcontainer configs.
@@ -634,15 +811,29 @@ pub fn pick_config(
     pick_wrapped::<ConfigItem>(configs, opts, on_delete)
 }
 
-/// Launches a picker for devcontainer selection.
+/// Launches a picker for devcontainer selection, listing and marking the remembered config first.
 pub fn pick_devcontainer(
     dev_containers: Vec<crate::workspace::DevContainer>,
+    remembered: Option<&Path>,
 ) -> Result<Option<crate::workspace::DevContainer>> {
     let opts = PickerOpts {
         hide_instructions: false,
         hide_info: false,
+        allow_attach: false,
     };
-    pick_wrapped::<DevContainerItem>(dev_containers, opts, None)
+    // the remembered dev container is listed first, so it's preselected
+    let mut items: Vec<_> = dev_containers
+        .into_iter()
+        .map(|dev_container| {
+            let is_remembered = remembered == Some(dev_container.config_path.as_path());
+            (dev_container, is_remembered)
+        })
+        .collect();
+    items.sort_by_key(|(_, is_remembered)| !is_remembered);
+    Ok(
+        pick_wrapped::<DevContainerItem>(items, opts, None)?
+            .map(|(dev_container, _)| dev_container),
+    )
 }
 
 fn pick_wrapped<T: WrappedPickable>(
@@ -662,3 +853,119 @@ fn add_num_opt(o1: Option<u32>, o2: Option<u32>) -> Option<u32> {
         _ => None,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::tests::test_entry;
+    use chrono::{Duration, Utc};
+
+    fn history() -> History {
+        let now = Utc::now();
+        History::from_entries(vec![
+            test_entry("frontend", None, now - Duration::hours(3)),
+            test_entry("backend", None, now),
+            test_entry("docs", None, now - Duration::hours(1)),
+            test_entry("backend-tools", None, now - Duration::hours(2)),
+        ])
+    }
+
+    fn name(found: &(EntryId, Entry)) -> &str {
+        &found.1.workspace_name
+    }
+
+    #[test]
+    fn test_find_last_nth() {
+        let history = history();
+        let names: Vec<_> = (1..=4)
+            .map(|nth| find_last(&history, nth, None).unwrap().1.workspace_name)
+            .collect();
+        assert_eq!(names, ["backend", "docs", "backend-tools", "frontend"]);
+
+        // a blank filter is ignored
+        assert_eq!(name(&find_last(&history, 2, Some(" ")).unwrap()), "docs");
+    }
+
+    #[test]
+    fn test_find_last_filter() {
+        let history = history();
+        assert_eq!(
+            name(&find_last(&history, 1, Some("front")).unwrap()),
+            "frontend"
+        );
+        assert_eq!(
+            name(&find_last(&history, 1, Some("back")).unwrap()),
+            "backend"
+        );
+        assert_eq!(
+            name(&find_last(&history, 2, Some("back")).unwrap()),
+            "backend-tools"
+        );
+    }
+
+    #[test]
+    fn test_find_history_order() {
+        let now = Utc::now();
+        let mut frontend = test_entry("frontend", None, now - Duration::days(7));
+        frontend.pinned = true;
+        let history = History::from_entries(vec![test_entry("backend", None, now), frontend]);
+        let names = |filter| -> Vec<String> {
+            find_history(&history, filter)
+                .into_iter()
+                .map(|(_, entry, _)| entry.workspace_name)
+                .collect()
+        };
+        // equally good matches are ranked like in the history UI
+        assert_eq!(names(Some("end")), ["frontend", "backend"]);
+        // without a filter, the pin doesn't matter
+        assert_eq!(names(None), ["backend", "frontend"]);
+    }
+
+    #[test]
+    fn test_find_last_no_match() {
+        let history = history();
+        let err = find_last(&history, 5, None).unwrap_err();
+        assert_eq!(err.to_string(), "No history entry #5 found.");
+        let err = find_last(&history, 3, Some("back")).unwrap_err();
+        assert_eq!(err.to_string(), "No history entry #3 matches `back`.");
+        let err = find_last(&history, 1, Some("xyz")).unwrap_err();
+        assert_eq!(err.to_string(), "No history entry #1 matches `xyz`.");
+        assert!(find_last(&History::default(), 1, None).is_err());
+    }
+
+    #[test]
+    fn test_is_clear_match_single() {
+        assert!(!is_clear_match("app", &[]));
+        assert!(is_clear_match("app", &[96]));
+        assert!(!is_clear_match("app", &[95]));
+        // whitespace separates the parts of the query and is not matched
+        assert!(is_clear_match("my app", &[160]));
+
+        // a lone match only found in the path is not clear
+        let history = History::from_entries(vec![test_entry("backend", None, Utc::now())]);
+        let scores = |query| -> Vec<u32> {
+            find_history(&history, Some(query))
+                .into_iter()
+                .map(|(.., score)| score)
+                .collect()
+        };
+        assert!(is_clear_match("bnd", &scores("bnd")));
+        assert_eq!(scores("pt").len(), 1);
+        assert!(!is_clear_match("pt", &scores("pt")));
+    }
+
+    #[test]
+    fn test_is_clear_match_ratio() {
+        assert!(is_clear_match("app", &[200, 100]));
+        assert!(is_clear_match("app", &[200, 100, 90]));
+        assert!(!is_clear_match("app", &[200, 101]));
+        // a clear lead is not enough for a weak match
+        assert!(!is_clear_match("app", &[90, 10]));
+    }
+
+    #[test]
+    fn test_is_clear_match_tie() {
+        assert!(!is_clear_match("app", &[160, 160]));
+        assert!(!is_clear_match("app", &[0, 0]));
+    }
+}
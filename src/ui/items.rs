@@ -2,6 +2,7 @@ use super::{Pickable, WrappedPickable};
 use crate::history::{Entry, EntryId};
 use chrono::{DateTime, Local};
 use ratatui::layout::Constraint;
+use ratatui::style::{Color, Style};
 use std::borrow::Cow;
 
 macro_rules! impl_wrapped_pickable {
@@ -20,10 +21,27 @@ macro_rules! impl_wrapped_pickable {
     };
 }
 
+/// Returns the location of the opened folder, including the subfolder of the workspace.
+fn opened_location(entry: &Entry) -> String {
+    match &entry.subfolder {
+        Some(subfolder) => format!("{}/{}", entry.location(), subfolder.display()),
+        None => entry.location(),
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct HistoryItem {
     pub id: EntryId,
     pub entry: Entry,
+    /// Whether the workspace or its config no longer exists (checked once, not on every render)
+    pub missing: bool,
+}
+
+impl HistoryItem {
+    pub fn new(id: EntryId, entry: Entry) -> Self {
+        let missing = entry.is_missing();
+        Self { id, entry, missing }
+    }
 }
 
 impl Pickable for HistoryItem {
@@ -42,15 +60,21 @@ impl Pickable for HistoryItem {
     }
 
     fn cells(&self) -> Vec<String> {
+        let workspace_name = format!(
+            "{}{}{}",
+            if self.entry.pinned { "★ " } else { "" },
+            if self.missing { "✗ " } else { "" },
+            self.entry.workspace_name
+        );
         vec![
-            self.entry.workspace_name.clone(),
+            workspace_name,
             self.entry
                 .dev_container_name
                 .as_deref()
                 .unwrap_or("")
                 .to_string(),
             self.entry.config_name.as_deref().unwrap_or("").to_string(),
-            self.entry.workspace_path.to_string_lossy().to_string(),
+            opened_location(&self.entry),
             DateTime::<Local>::from(self.entry.last_opened)
                 .format("%Y-%m-%d %H:%M:%S")
                 .to_string(),
@@ -62,7 +86,7 @@ impl Pickable for HistoryItem {
             self.entry.workspace_name.clone(),
             self.entry.dev_container_name.clone().unwrap_or_default(),
             self.entry.config_name.clone().unwrap_or_default(),
-            self.entry.workspace_path.to_string_lossy().to_string(),
+            opened_location(&self.entry),
         ]
     }
 
@@ -82,16 +106,30 @@ impl Pickable for HistoryItem {
             .as_ref()
             .map(|path| path.to_string_lossy().into_owned())
             .unwrap_or_default();
+        let container_folder = self
+            .entry
+            .container_folder
+            .as_deref()
+            .map(|folder| format!(" • Folder: {folder}"))
+            .unwrap_or_default();
 
         vec![
             format!(
                 "Strategy: {} • Command: {} • Args ({args_count}): {args_joined}",
                 self.entry.behavior.strategy, self.entry.behavior.command,
             ),
-            format!("Dev Container: {config_path}"),
+            format!("Dev Container: {config_path}{container_folder}"),
         ]
     }
 
+    fn style(&self) -> Style {
+        if self.missing {
+            Style::default().fg(Color::DarkGray)
+        } else {
+            Style::default()
+        }
+    }
+
     fn column_constraints(max_widths: &[usize]) -> Vec<Constraint> {
         let workspace_width = max_widths.first().copied().unwrap_or(20).clamp(9, 60);
         let devcontainer_width = max_widths.get(1).copied().unwrap_or(20).clamp(9, 60);
@@ -135,7 +173,14 @@ impl Pickable for ContainerItem {
     }
 
     fn status_lines(&self) -> Vec<String> {
-        vec![format!("Config: {}", self.0.config_file)]
+        let mut lines = vec![format!("Config: {}", self.0.config_file)];
+        if let Some(project) = &self.0.compose_project {
+            lines.push(format!(
+                "Compose: {project} • Service: {}",
+                self.0.compose_service.as_deref().unwrap_or("unknown")
+            ));
+        }
+        lines
     }
 
     fn column_constraints(max_widths: &[usize]) -> Vec<Constraint> {
@@ -197,7 +242,11 @@ impl Pickable for ConfigItem {
 impl_wrapped_pickable!(ConfigItem, crate::config_store::ConfigEntry);
 
 #[derive(Clone, Debug)]
-pub struct DevContainerItem(pub crate::workspace::DevContainer);
+pub struct DevContainerItem {
+    pub dev_container: crate::workspace::DevContainer,
+    /// Whether this dev container was used the last time the workspace was opened.
+    pub remembered: bool,
+}
 
 impl Pickable for DevContainerItem {
     fn title() -> &'static str {
@@ -205,34 +254,75 @@ impl Pickable for DevContainerItem {
     }
 
     fn headers() -> &'static [&'static str] {
-        &["Name", "Config Path"]
+        &["Name", "Service", "Config Path"]
     }
 
     fn cells(&self) -> Vec<String> {
+        let name = self.dev_container.name.as_deref().unwrap_or("(unnamed)");
         vec![
-            self.0.name.as_deref().unwrap_or("(unnamed)").to_string(),
-            self.0.config_path.display().to_string(),
+            if self.remembered {
+                format!("{name} (last used)")
+            } else {
+                name.to_string()
+            },
+            self.dev_container
+                .compose
+                .as_ref()
+                .map(|compose| compose.service.clone())
+                .unwrap_or_default(),
+            self.dev_container.config_path.display().to_string(),
         ]
     }
 
     fn search_fields(&self) -> Vec<String> {
-        vec![
-            self.0.name.clone().unwrap_or_default(),
-            self.0.config_path.to_string_lossy().to_string(),
-        ]
+        self.cells()
     }
 
     fn status_lines(&self) -> Vec<String> {
-        vec![format!("Workspace: {}", self.0.workspace_path_in_container)]
+        let mut lines = vec![format!(
+            "Workspace: {}",
+            self.dev_container.workspace_path_in_container
+        )];
+        let config = &self.dev_container.config;
+        if let Some(image) = &config.image {
+            lines.push(format!("Image: {image}"));
+        } else if let Some(dockerfile) = config.build.as_ref().and_then(|b| b.dockerfile.as_ref()) {
+            lines.push(format!("Dockerfile: {dockerfile}"));
+        }
+        if let Some(compose) = &self.dev_container.compose {
+            let files = compose
+                .files
+                .iter()
+                .map(|file| file.display().to_string())
+                .collect::<Vec<_>>()
+                .join(", ");
+            lines.push(format!("Compose files: {files}"));
+        }
+        lines
     }
 
     fn column_constraints(max_widths: &[usize]) -> Vec<Constraint> {
         let name_width = max_widths.first().copied().unwrap_or(9).clamp(9, 40);
+        let service_width = max_widths.get(1).copied().unwrap_or(7).clamp(7, 30);
         vec![
             Constraint::Min(u16::try_from(name_width).unwrap_or(9)),
+            Constraint::Min(u16::try_from(service_width).unwrap_or(7)),
             Constraint::Percentage(80),
         ]
     }
 }
 
-impl_wrapped_pickable!(DevContainerItem, crate::workspace::DevContainer);
+impl WrappedPickable for DevContainerItem {
+    type Inner = (crate::workspace::DevContainer, bool);
+
+    fn from_inner((dev_container, remembered): Self::Inner) -> Self {
+        Self {
+            dev_container,
+            remembered,
+        }
+    }
+
+    fn into_inner(self) -> Self::Inner {
+        (self.dev_container, self.remembered)
+    }
+}
@@ -0,0 +1,107 @@
+use color_eyre::eyre::Result;
+use std::fmt::Display;
+use std::io::{self, BufRead, IsTerminal, Write};
+
+/// Whether questions can be asked on the terminal (through stdin and stderr).
+pub fn is_interactive() -> bool {
+    io::stdin().is_terminal() && io::stderr().is_terminal()
+}
+
+/// Asks a yes/no question on the terminal, which is declined without a terminal.
+pub fn confirm(question: &str) -> Result<bool> {
+    if !is_interactive() {
+        return Ok(false);
+    }
+    let no_options: [&str; 0] = [];
+    let answer = ask(
+        &mut io::stdin().lock(),
+        &mut io::stderr(),
+        &no_options,
+        question,
+    )?;
+    Ok(answer.is_some())
+}
+
+/// Lists the options and asks to choose one of them on the terminal.
+///
+/// Returns the index of the chosen option, `None` if none was chosen or there is no terminal.
+pub fn choose(options: &[impl Display], question: &str) -> Result<Option<usize>> {
+    if options.is_empty() || !is_interactive() {
+        return Ok(None);
+    }
+    ask(
+        &mut io::stdin().lock(),
+        &mut io::stderr(),
+        options,
+        question,
+    )
+}
+
+/// Asks the question with the options, accepting `y` for a single option or without options.
+fn ask(
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+    options: &[impl Display],
+    question: &str,
+) -> Result<Option<usize>> {
+    for (index, option) in options.iter().enumerate() {
+        writeln!(output, "  {}) {option}", index + 1)?;
+    }
+    if options.len() > 1 {
+        write!(output, "{question} [1-{}/N] ", options.len())?;
+    } else {
+        write!(output, "{question} [y/N] ")?;
+    }
+    output.flush()?;
+
+    let mut answer = String::new();
+    input.read_line(&mut answer)?;
+    let answer = answer.trim();
+    if options.len() <= 1 && answer.eq_ignore_ascii_case("y") {
+        return Ok(Some(0));
+    }
+    Ok(answer
+        .parse::<usize>()
+        .ok()
+        .and_then(|number| number.checked_sub(1))
+        .filter(|index| *index < options.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn answer(options: &[&str], input: &str) -> (Option<usize>, String) {
+        let mut output = Vec::new();
+        let chosen = ask(&mut input.as_bytes(), &mut output, options, "Relocate?").unwrap();
+        (chosen, String::from_utf8(output).unwrap())
+    }
+
+    #[test]
+    fn test_confirm() {
+        assert_eq!(
+            answer(&[], "y\n"),
+            (Some(0), "Relocate? [y/N] ".to_string())
+        );
+        assert_eq!(answer(&[], "Y\n").0, Some(0));
+        assert_eq!(answer(&[], "\n").0, None);
+        assert_eq!(answer(&[], "yes\n").0, None);
+        assert_eq!(answer(&[], "").0, None);
+    }
+
+    #[test]
+    fn test_choose() {
+        let (chosen, output) = answer(&["/a", "/b"], "2\n");
+        assert_eq!(chosen, Some(1));
+        assert_eq!(output, "  1) /a\n  2) /b\nRelocate? [1-2/N] ");
+        assert_eq!(answer(&["/a", "/b"], "y\n").0, None);
+        assert_eq!(answer(&["/a", "/b"], "3\n").0, None);
+        assert_eq!(answer(&["/a", "/b"], "0\n").0, None);
+
+        let (chosen, output) = answer(&["/a"], " y \n");
+        assert_eq!(chosen, Some(0));
+        assert_eq!(output, "  1) /a\nRelocate? [y/N] ");
+        assert_eq!(answer(&["/a"], "1\n").0, Some(0));
+        assert_eq!(answer(&["/a"], "n\n").0, None);
+    }
+}
@@ -4,7 +4,8 @@ use url::Url;
 /// Represents a single file path to a dev container config as expected by the code CLI.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct FileUriJson {
-    path: Url,
+    scheme: &'static str,
+    path: String,
     authority: Option<String>,
 }
 
@@ -17,8 +18,18 @@ impl FileUriJson {
         let parsed_url = Url::parse(&fixed_uri).expect("Invalid URI");
 
         Self {
+            scheme: "file",
             authority: parsed_url.host_str().map(ToString::to_string),
-            path: parsed_url,
+            path: parsed_url.path().to_owned(),
+        }
+    }
+
+    /// Creates a new `FileUri` pointing to a file on a remote ssh host
+    pub fn ssh_remote(host: &str, path: &str) -> Self {
+        Self {
+            scheme: "vscode-remote",
+            authority: Some(format!("ssh-remote+{host}")),
+            path: path.to_owned(),
         }
     }
 }
@@ -27,11 +38,11 @@ impl Serialize for FileUriJson {
     /// Creates the JSON representation of the `FileUri`.
     fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
         let mut map = serializer.serialize_map(None)?;
-        map.serialize_entry("scheme", "file")?;
+        map.serialize_entry("scheme", self.scheme)?;
         if let Some(authority) = &self.authority {
             map.serialize_entry("authority", authority)?;
         }
-        map.serialize_entry("path", self.path.path())?;
+        map.serialize_entry("path", &self.path)?;
         map.end()
     }
 }
@@ -46,3 +57,49 @@ pub struct DevcontainerUriJson {
     #[serde(rename = "configFile")]
     pub config_file: FileUriJson,
 }
+
+/// Represents an attached container launch argument as expected by the code CLI.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+pub struct AttachedContainerUriJson {
+    /// The name of the container, prefixed with a `/` like Docker reports it
+    #[serde(rename = "containerName")]
+    pub container_name: String,
+}
+
+impl AttachedContainerUriJson {
+    /// Creates a new `AttachedContainerUriJson` from a container name or ID
+    pub fn new(container: &str) -> Self {
+        Self {
+            container_name: format!("/{}", container.trim_start_matches('/')),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ssh_remote_devcontainer_json() {
+        let uri = DevcontainerUriJson {
+            host_path: "/home/me/project".to_string(),
+            config_file: FileUriJson::ssh_remote(
+                "buildbox",
+                "/home/me/project/.devcontainer/devcontainer.json",
+            ),
+        };
+        assert_eq!(
+            serde_json::to_string(&uri).unwrap(),
+            r#"{"hostPath":"/home/me/project","configFile":{"scheme":"vscode-remote","authority":"ssh-remote+buildbox","path":"/home/me/project/.devcontainer/devcontainer.json"}}"#
+        );
+    }
+
+    #[test]
+    fn test_attached_container_json() {
+        let json = serde_json::to_string(&AttachedContainerUriJson::new("my_container")).unwrap();
+        assert_eq!(json, r#"{"containerName":"/my_container"}"#);
+
+        let json = serde_json::to_string(&AttachedContainerUriJson::new("/my_container")).unwrap();
+        assert_eq!(json, r#"{"containerName":"/my_container"}"#);
+    }
+}
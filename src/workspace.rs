@@ -1,83 +1,181 @@
 use color_eyre::eyre::{Result, WrapErr, bail, eyre};
-use log::{debug, trace};
+use log::{debug, info, trace, warn};
+use serde::{Deserialize, Serialize};
 use std::ffi::OsString;
 use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::process::{Command, Stdio};
 use walkdir::WalkDir;
 
+use crate::editor::{Editor, LaunchMode, find_in_path};
+
+use mount::WorkspaceMount;
+use variables::Variables;
+
+mod config;
+mod goto;
+mod mount;
 mod open;
+mod remote;
+mod variables;
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub use config::{DevContainerConfig, VscliCustomizations};
+pub use goto::Goto;
+pub use open::open_attached;
+pub use remote::is_ssh_uri;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct DevContainer {
     pub config_path: PathBuf,
     pub name: Option<String>,
     pub workspace_path_in_container: String,
+    /// The Docker Compose setup, if the dev container is compose based.
+    pub compose: Option<ComposeConfig>,
+    /// The parsed config, with all variables substituted.
+    pub config: DevContainerConfig,
+    /// The vscli settings from `customizations.vscli`.
+    pub customizations: VscliCustomizations,
+}
+
+/// The Docker Compose properties of a dev container config.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ComposeConfig {
+    /// The compose files, resolved relative to the dev container config.
+    pub files: Vec<PathBuf>,
+    /// The primary service vscode connects to.
+    pub service: String,
+    /// The services that are started, if not all of them.
+    pub run_services: Vec<String>,
+}
+
+impl ComposeConfig {
+    /// Reads the compose properties from a parsed dev container config.
+    fn from_config(config_path: &Path, config: &DevContainerConfig) -> Result<Option<Self>> {
+        let Some(files) = &config.docker_compose_file else {
+            return Ok(None);
+        };
+
+        let config_dir = config_path.parent().unwrap_or_else(|| Path::new(""));
+        let files = files
+            .to_vec()
+            .into_iter()
+            .map(|file| config_dir.join(file))
+            .collect();
+
+        let service = config.service.clone().ok_or_else(|| {
+            eyre!(
+                "Dev container config uses `dockerComposeFile` but does not specify a `service`: {}",
+                config_path.display()
+            )
+        })?;
+
+        Ok(Some(Self {
+            files,
+            service,
+            run_services: config.run_services.clone().unwrap_or_default(),
+        }))
+    }
+
+    /// Makes sure all referenced compose files exist.
+    pub fn validate(&self) -> Result<()> {
+        for file in &self.files {
+            if !file.is_file() {
+                bail!("Docker Compose file does not exist: {}", file.display());
+            }
+        }
+        Ok(())
+    }
 }
 
 impl DevContainer {
-    /// Creates a new `DevContainer` from a dev container config file and fallback workspace name.
-    pub fn from_config(path: &Path, workspace_name: &str) -> Result<DevContainer> {
-        let dev_container = Self::parse_dev_container_config(path)?;
-        trace!("dev container config: {dev_container:?}");
+    /// Returns `<dir>` for configs located at `.devcontainer/<dir>/devcontainer.json`.
+    pub fn config_dir_name(&self) -> Option<String> {
+        let dir = self.config_path.parent()?;
+        dir.parent()
+            .and_then(Path::file_name)
+            .is_some_and(|name| name == ".devcontainer")
+            .then(|| dir.file_name())
+            .flatten()
+            .map(|name| name.to_string_lossy().into_owned())
+    }
+
+    /// Creates a new `DevContainer` from a dev container config file and the host workspace folder.
+    pub fn from_config(path: &Path, workspace_folder: &Path) -> Result<DevContainer> {
+        let config = DevContainerConfig::from_file(path)?;
+        Self::from_parsed_config(path, &config, workspace_folder)
+    }
+
+    /// Creates a new `DevContainer` from a parsed dev container config and the host workspace folder.
+    fn from_parsed_config(
+        path: &Path,
+        config: &DevContainerConfig,
+        workspace_folder: &Path,
+    ) -> Result<DevContainer> {
+        trace!("dev container config: {config:?}");
 
-        let folder: String = if let Some(folder) = dev_container["workspaceFolder"].as_str() {
+        let mut variables = Variables::new(workspace_folder, path);
+
+        // the workspace folder has to be resolved first, since other fields can reference it
+        let folder: String = if let Some(folder) = &config.workspace_folder {
             debug!("Read workspace folder from config: {folder}");
-            // Substitute variables in the workspace folder path
-            Self::substitute_variables(folder, workspace_name)
+            variables.substitute(folder)
+        } else if let Some(mount) = &config.workspace_mount {
+            debug!("Read workspace mount from config: {mount}");
+            let mount = WorkspaceMount::parse(&variables.substitute(mount))
+                .wrap_err_with(|| format!("Invalid dev container config: {}", path.display()))?;
+            trace!("Workspace mount: {mount:?}");
+            mount.container_path(workspace_folder)
         } else {
             debug!("Could not read workspace folder from config -> using default folder");
-            format!("/workspaces/{workspace_name}")
+            variables.default_container_workspace_folder()
         };
         trace!("Workspace folder: {folder}");
 
-        let name = if let Some(name) = dev_container["name"].as_str() {
+        variables.set_container_workspace_folder(&folder);
+        let config = config.substitute(&variables)?;
+
+        let customizations = config.vscli_customizations();
+        trace!("vscli customizations: {customizations:?}");
+
+        let name = if let Some(name) = &customizations.display_name {
+            debug!("Read display name from vscli customizations: {name}");
+            Some(name.clone())
+        } else if let Some(name) = &config.name {
             debug!("Read workspace name from config: {name}");
-            Some(name.to_owned())
+            Some(name.clone())
         } else {
             debug!("Could not read workspace name from config");
             None
         };
         trace!("Workspace name: {name:?}");
 
+        let compose = ComposeConfig::from_config(path, &config)?;
+        trace!("Compose config: {compose:?}");
+
         Ok(DevContainer {
             config_path: path.to_owned(),
             workspace_path_in_container: folder,
             name,
+            compose,
+            config,
+            customizations,
         })
     }
+}
 
-    /// Parses the dev container config file.
-    /// `https://code.visualstudio.com/remote/advancedcontainers/change-default-source-mount`
-    fn parse_dev_container_config(path: &Path) -> Result<serde_json::Value> {
-        let path_log = path.display();
-
-        let content = std::fs::read_to_string(path)
-            .wrap_err_with(|| format!("Failed to read dev container config file: {path_log}"))?;
-
-        let config: serde_json::Value = json5::from_str(&content)
-            .wrap_err_with(|| format!("Failed to parse json file: {path_log}"))?;
-
-        debug!("Parsed dev container config: {path_log}");
-        Ok(config)
-    }
-
-    /// Substitutes variables in the workspace folder path.
-    /// Supports the following variables:
-    /// - ${localWorkspaceFolderBasename} - The name of the workspace folder
-    /// - ${localWorkspaceFolder} - The full path to the workspace folder (defaults to /workspaces/<name>)
-    fn substitute_variables(folder: &str, workspace_name: &str) -> String {
-        let mut result = folder.to_owned();
-
-        // Replace ${localWorkspaceFolderBasename} with the workspace name
-        result = result.replace("${localWorkspaceFolderBasename}", workspace_name);
-
-        // Replace ${localWorkspaceFolder} with the full workspace path
-        // This defaults to /workspaces/<workspace_name> for consistency
-        let default_workspace = format!("/workspaces/{workspace_name}");
-        result = result.replace("${localWorkspaceFolder}", &default_workspace);
-
-        result
-    }
+/// The file extension of vscode multi-root workspace files.
+const WORKSPACE_FILE_EXTENSION: &str = "code-workspace";
+
+/// Describes what kind of target a workspace path points to.
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum WorkspaceKind {
+    /// A plain project folder.
+    #[default]
+    Folder,
+    /// A `.code-workspace` multi-root workspace file.
+    File,
 }
 
 /// A workspace is a folder which contains a vscode project.
@@ -87,6 +185,12 @@ pub struct Workspace {
     pub path: PathBuf,
     /// The name of the workspace.
     pub name: String,
+    /// Whether the path is a folder or a `.code-workspace` file.
+    pub kind: WorkspaceKind,
+    /// The ssh host the workspace lives on, if it is not a local workspace.
+    pub host: Option<String>,
+    /// A file to focus after opening the workspace.
+    pub goto: Option<Goto>,
 }
 
 impl Workspace {
@@ -103,22 +207,44 @@ impl Workspace {
             .wrap_err_with(|| format!("Error canonicalizing path: {path_log}"))?;
         trace!("Canonicalized path: {path_log}");
 
-        // get workspace name (either directory or file name)
-        let workspace_name = path
-            .file_name()
-            .ok_or_else(|| eyre!("Error getting workspace from path"))?
-            .to_string_lossy()
-            .into_owned();
+        let kind = if is_workspace_file(&path) {
+            WorkspaceKind::File
+        } else {
+            WorkspaceKind::Folder
+        };
+        trace!("Workspace kind: {kind:?}");
+
+        // get workspace name (either directory or file name, without the workspace file extension)
+        let workspace_name = match kind {
+            WorkspaceKind::Folder => path.file_name(),
+            WorkspaceKind::File => path.file_stem(),
+        }
+        .ok_or_else(|| eyre!("Error getting workspace from path"))?
+        .to_string_lossy()
+        .into_owned();
         trace!("Workspace name: {workspace_name}");
 
         let ws = Workspace {
             path,
             name: workspace_name,
+            kind,
+            host: None,
+            goto: None,
         };
         trace!("{ws:?}");
         Ok(ws)
     }
 
+    /// Returns the folder that is mounted into the dev container.
+    ///
+    /// For workspace files this is the folder containing the file.
+    pub fn folder(&self) -> &Path {
+        match self.kind {
+            WorkspaceKind::Folder => &self.path,
+            WorkspaceKind::File => self.path.parent().unwrap_or(&self.path),
+        }
+    }
+
     /// Finds all dev container configs in the workspace.
     ///
     /// # Note
@@ -127,19 +253,26 @@ impl Workspace {
     /// - A `.devcontainer/devcontainer.json` defined in the `.devcontainer/` folder.
     /// - Any `.devcontainer/**/devcontainer.json` file in any `.devcontainer/` subfolder (only one level deep).
     ///
+    /// For workspace files, the folder containing the file is searched.
+    ///
     /// This should results in a dev container detection algorithm similar to the one vscode uses.
     pub fn find_dev_container_configs(&self) -> Vec<PathBuf> {
+        if let Some(host) = &self.host {
+            return self.find_remote_dev_container_configs(host);
+        }
+
         let mut configs = Vec::new();
+        let folder = self.folder();
 
         // check if we have a `devcontainer.json` directly in the workspace
-        let direct_config = self.path.join(".devcontainer.json");
+        let direct_config = folder.join(".devcontainer.json");
         if direct_config.is_file() {
             trace!("Found dev container config: {}", direct_config.display());
             configs.push(direct_config);
         }
 
         // check configs one level deep in `.devcontainer/`
-        let dev_container_dir = self.path.join(".devcontainer");
+        let dev_container_dir = folder.join(".devcontainer");
         for entry in WalkDir::new(dev_container_dir)
             .max_depth(2)
             .sort_by_file_name()
@@ -168,67 +301,175 @@ impl Workspace {
         // parse dev containers and their properties
         paths
             .iter()
-            .map(|config_path| DevContainer::from_config(config_path, &self.name))
+            .map(|config_path| self.load_dev_container(config_path))
             .collect::<Result<Vec<_>, _>>()
     }
 
+    /// Loads a single dev container config, reading it from the remote host if necessary.
+    pub fn load_dev_container(&self, config_path: &Path) -> Result<DevContainer> {
+        match &self.host {
+            Some(host) => DevContainer::from_remote_config(host, config_path, self.folder()),
+            None => DevContainer::from_config(config_path, self.folder()),
+        }
+    }
+
     /// Open vscode like with the `code` command
     pub fn open_classic(
         &self,
         mut args: Vec<OsString>,
         dry_run: bool,
-        command: &str,
+        mode: LaunchMode,
+        editor: &Editor,
     ) -> Result<()> {
         trace!("path: {}", self.path.display());
         trace!("args: {args:?}");
 
-        args.insert(0, self.path.as_os_str().to_owned());
-        exec_code(args, dry_run, command)
-            .wrap_err_with(|| "Error opening vscode the classic way...")
+        if let Some(host) = &self.host {
+            let uri = format!("vscode-remote://ssh-remote+{host}{}", self.path.display());
+            args.push(OsString::from(&editor.folder_uri_flag));
+            args.push(OsString::from(uri));
+        } else {
+            args.insert(0, self.path.as_os_str().to_owned());
+        }
+
+        if let Some(goto) = &self.goto {
+            let mut file = goto.file.as_os_str().to_owned();
+            file.push(goto.position_suffix());
+            args.push(OsString::from("--goto"));
+            args.push(file);
+        }
+        exec_code(args, dry_run, mode, editor)
+            .wrap_err_with(|| format!("Error opening {} the classic way...", editor.name))
+    }
+}
+
+/// Finds the nearest directory (starting with `start` itself) that contains a dev container config.
+///
+/// The search stops at the git repository root or the home directory.
+pub fn find_dev_container_root(start: &Path) -> Option<PathBuf> {
+    let home = dirs::home_dir();
+    for dir in start.ancestors() {
+        if dir.join(".devcontainer").is_dir() || dir.join(".devcontainer.json").is_file() {
+            return Some(dir.to_path_buf());
+        }
+        if dir.join(".git").exists() || home.as_deref() == Some(dir) {
+            trace!(
+                "Stopped looking for a dev container config at {}",
+                dir.display()
+            );
+            break;
+        }
     }
+    None
+}
+
+/// Checks whether the path points to a `.code-workspace` multi-root workspace file.
+pub fn is_workspace_file(path: &Path) -> bool {
+    path.is_file()
+        && path
+            .extension()
+            .is_some_and(|ext| ext == WORKSPACE_FILE_EXTENSION)
 }
 
-/// Executes the vscode executable with the given arguments on Unix.
+/// Executes the editor with the given arguments on Unix.
 #[cfg(unix)]
-fn exec_code(args: Vec<OsString>, dry_run: bool, command: &str) -> Result<()> {
-    // test if cmd exists
-    Command::new(command)
-        .arg("-v")
-        .output()
-        .wrap_err_with(|| format!("`{command}` does not exists."))?;
-
-    run(command, args, dry_run)
+fn exec_code(args: Vec<OsString>, dry_run: bool, mode: LaunchMode, editor: &Editor) -> Result<()> {
+    let command = editor.command.as_str();
+    if find_in_path(command).is_none() {
+        bail!("`{command}` ({}) was not found in PATH.", editor.name);
+    }
+
+    run(command, args, dry_run, mode)
 }
 
-/// Executes the vscode executable with the given arguments on Windows.
+/// Executes the editor with the given arguments on Windows.
 #[cfg(windows)]
-fn exec_code(mut args: Vec<OsString>, dry_run: bool, command: &str) -> Result<()> {
+fn exec_code(
+    mut args: Vec<OsString>,
+    dry_run: bool,
+    mode: LaunchMode,
+    editor: &Editor,
+) -> Result<()> {
+    let command = editor.command.as_str();
+    if find_in_path(command).is_none() {
+        bail!("`{command}` ({}) was not found in PATH.", editor.name);
+    }
+
+    // editors are usually installed as batch scripts, which have to be run through `cmd`
     let cmd = "cmd";
     args.insert(0, OsString::from("/c"));
     args.insert(1, OsString::from(command));
 
-    // test if cmd exists
-    Command::new(cmd)
-        .arg("-v")
-        .output()
-        .wrap_err_with(|| format!("`{cmd}` does not exists."))?;
-
-    run(cmd, args, dry_run)
+    run(cmd, args, dry_run, mode)
 }
 
 /// Executes a command with given arguments and debug outputs, with an option for dry run
-fn run(cmd: &str, args: Vec<OsString>, dry_run: bool) -> Result<()> {
+fn run(cmd: &str, mut args: Vec<OsString>, dry_run: bool, mode: LaunchMode) -> Result<()> {
+    if mode == LaunchMode::Wait {
+        args.push(OsString::from("--wait"));
+    }
     debug!("executable: {cmd}");
     debug!("final args: {args:?}");
 
-    if !dry_run {
-        let output = Command::new(cmd).args(args).output()?;
-        debug!("Command output: {output:?}");
+    if dry_run {
+        return Ok(());
+    }
+
+    let command_line = command_line(cmd, &args);
+    let mut command = Command::new(cmd);
+    command.args(args).stdin(Stdio::null());
+    match mode {
+        LaunchMode::Detach => {
+            let child = command
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .spawn()
+                .wrap_err_with(|| format!("Failed to start `{command_line}`"))?;
+            debug!("Started detached process {}", child.id());
+        }
+        LaunchMode::Check | LaunchMode::Wait => {
+            if mode == LaunchMode::Wait {
+                info!("Waiting for the editor window to be closed...");
+            }
+            let output = command
+                .output()
+                .wrap_err_with(|| format!("Failed to run `{command_line}`"))?;
+            debug!("Command output: {output:?}");
+
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let stderr = stderr.trim();
+            if !output.status.success() {
+                let mut message = format!("`{command_line}` failed ({})", output.status);
+                if !stderr.is_empty() {
+                    message.push_str(":\n");
+                    message.push_str(stderr);
+                }
+                bail!(message);
+            }
+            if !stderr.is_empty() {
+                warn!("The editor reported:\n{stderr}");
+            }
+        }
     }
 
     Ok(())
 }
 
+/// Formats a command and its arguments as a shell-like command line for messages.
+fn command_line(cmd: &str, args: &[OsString]) -> String {
+    std::iter::once(cmd.into())
+        .chain(args.iter().map(|arg| arg.to_string_lossy()))
+        .map(|part| {
+            if part.is_empty() || part.contains(char::is_whitespace) {
+                format!("\"{part}\"")
+            } else {
+                part.into_owned()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
 /// Converts a Docker label path (which may be a Windows/WSL path) to a local filesystem path.
 #[cfg(unix)]
 pub fn resolve_local_path(path: &str) -> String {
@@ -254,7 +495,7 @@ mod tests {
     #[test]
     fn test_deserialize_devcontainer() {
         let path = PathBuf::from("tests/fixtures/devcontainer.json");
-        let result = DevContainer::from_config(&path, "test");
+        let result = DevContainer::from_config(&path, Path::new("/projects/test"));
         assert!(result.is_ok());
         let dev_container = result.unwrap();
 
@@ -282,31 +523,163 @@ mod tests {
         )
         .unwrap();
 
-        let dev_container = DevContainer::from_config(&path, "test").unwrap();
+        let dev_container = DevContainer::from_config(&path, Path::new("/projects/test")).unwrap();
 
         assert_eq!(dev_container.name, Some(String::from("Rust")));
     }
 
     #[test]
-    fn test_substitute_variables() {
-        // Test ${localWorkspaceFolderBasename} substitution
-        let folder = "/workspaces/${localWorkspaceFolderBasename}";
-        let result = DevContainer::substitute_variables(folder, "my-project");
-        assert_eq!(result, "/workspaces/my-project");
-
-        // Test ${localWorkspaceFolder} substitution
-        let folder = "${localWorkspaceFolder}/src";
-        let result = DevContainer::substitute_variables(folder, "my-project");
-        assert_eq!(result, "/workspaces/my-project/src");
-
-        // Test combination of variables
-        let folder = "${localWorkspaceFolder}/${localWorkspaceFolderBasename}-test";
-        let result = DevContainer::substitute_variables(folder, "my-project");
-        assert_eq!(result, "/workspaces/my-project/my-project-test");
-
-        // Test no variables
-        let folder = "/custom/path";
-        let result = DevContainer::substitute_variables(folder, "my-project");
-        assert_eq!(result, "/custom/path");
+    fn test_deserialize_compose_devcontainer() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path().join(".devcontainer");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("docker-compose.yml"), "services: {}\n").unwrap();
+        let path = dir.join("devcontainer.json");
+        std::fs::write(
+            &path,
+            r#"{
+                "name": "Compose",
+                "dockerComposeFile": ["docker-compose.yml", "../docker-compose.extend.yml"],
+                "service": "app",
+                "runServices": ["app", "db"],
+            }"#,
+        )
+        .unwrap();
+
+        let other = temp.path().join(".devcontainer/other/devcontainer.json");
+        std::fs::create_dir_all(other.parent().unwrap()).unwrap();
+        std::fs::write(&other, r#"{ "name": "Other", "image": "rust" }"#).unwrap();
+
+        // the second compose file is missing, which only fails when that dev container is opened
+        let workspace = Workspace::from_path(temp.path()).unwrap();
+        let configs = workspace.find_dev_container_configs();
+        let dev_containers = workspace.load_dev_containers(&configs).unwrap();
+        assert_eq!(dev_containers.len(), 2);
+        // any command in the PATH works, since nothing is run in a dry run
+        let editor = crate::editor::EditorRegistry::default().resolve("true");
+        for dev_container in &dev_containers {
+            let result = workspace.open(
+                vec![],
+                true,
+                LaunchMode::Check,
+                dev_container,
+                &editor,
+                None,
+            );
+            if dev_container.compose.is_some() {
+                let error = result.unwrap_err();
+                assert!(error.to_string().contains("docker-compose.extend.yml"));
+            } else {
+                result.unwrap();
+            }
+        }
+
+        std::fs::write(temp.path().join("docker-compose.extend.yml"), "").unwrap();
+        let compose = DevContainer::from_config(&path, Path::new("/projects/test"))
+            .unwrap()
+            .compose
+            .unwrap();
+        assert_eq!(compose.service, "app");
+        assert_eq!(compose.run_services, vec!["app", "db"]);
+        assert_eq!(compose.files[0], dir.join("docker-compose.yml"));
+    }
+
+    #[test]
+    fn test_workspace_folder_from_workspace_mount() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("devcontainer.json");
+        std::fs::write(
+            &path,
+            r#"{
+                "workspaceMount": "source=${localWorkspaceFolder}/..,target=/src,type=bind",
+            }"#,
+        )
+        .unwrap();
+
+        let dev_container =
+            DevContainer::from_config(&path, Path::new("/home/me/my-project")).unwrap();
+
+        assert_eq!(dev_container.workspace_path_in_container, "/src/my-project");
+    }
+
+    #[test]
+    fn test_workspace_file_uses_parent_folder() {
+        let temp = tempfile::tempdir().unwrap();
+        let project = temp.path().join("project");
+        std::fs::create_dir_all(project.join(".devcontainer")).unwrap();
+        std::fs::write(
+            project.join(".devcontainer").join("devcontainer.json"),
+            "{}",
+        )
+        .unwrap();
+        let file = project.join("multi.code-workspace");
+        std::fs::write(&file, "{}").unwrap();
+
+        let workspace = Workspace::from_path(&file).unwrap();
+
+        assert_eq!(workspace.kind, WorkspaceKind::File);
+        assert_eq!(workspace.name, "multi");
+        assert_eq!(workspace.folder(), project.canonicalize().unwrap());
+        assert_eq!(workspace.find_dev_container_configs().len(), 1);
+    }
+
+    #[test]
+    fn test_substitute_variables_in_config() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("devcontainer.json");
+        std::fs::write(
+            &path,
+            r#"{
+                "name": "${localWorkspaceFolderBasename} in ${containerWorkspaceFolderBasename}",
+                "workspaceFolder": "/src/${localWorkspaceFolderBasename}",
+            }"#,
+        )
+        .unwrap();
+
+        let dev_container =
+            DevContainer::from_config(&path, Path::new("/home/me/my-project")).unwrap();
+
+        assert_eq!(dev_container.workspace_path_in_container, "/src/my-project");
+        assert_eq!(
+            dev_container.name.as_deref(),
+            Some("my-project in my-project")
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_reports_failure() {
+        let args = vec![
+            OsString::from("-c"),
+            OsString::from("echo 'unknown option' >&2; exit 3"),
+        ];
+        let error = run("sh", args.clone(), false, LaunchMode::Check).unwrap_err();
+        let message = error.to_string();
+        assert!(message.starts_with(r#"`sh -c "echo 'unknown option' >&2; exit 3"` failed"#));
+        assert!(message.ends_with("unknown option"));
+
+        assert!(run("sh", args, true, LaunchMode::Check).is_ok());
+    }
+
+    #[test]
+    fn test_find_dev_container_root() {
+        let temp = tempfile::tempdir().unwrap();
+        let repo = temp.path().join("repo");
+        let nested = repo.join("crates/foo");
+        std::fs::create_dir_all(&nested).unwrap();
+        std::fs::create_dir_all(repo.join(".devcontainer")).unwrap();
+        std::fs::create_dir_all(repo.join(".git")).unwrap();
+
+        assert_eq!(find_dev_container_root(&nested), Some(repo.clone()));
+        assert_eq!(find_dev_container_root(&repo), Some(repo.clone()));
+
+        // configs outside of the repository are not used
+        let inner = repo.join("vendor/lib");
+        std::fs::create_dir_all(inner.join("src")).unwrap();
+        std::fs::write(inner.join(".git"), "gitdir: ../../.git/modules/lib").unwrap();
+        std::fs::remove_dir_all(repo.join(".devcontainer")).unwrap();
+        std::fs::write(temp.path().join(".devcontainer.json"), "{}").unwrap();
+        assert_eq!(find_dev_container_root(&inner.join("src")), None);
+        assert_eq!(find_dev_container_root(&nested), None);
     }
 }
@@ -0,0 +1,246 @@
+use color_eyre::eyre::{Result, WrapErr};
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use super::variables::Variables;
+
+/// The typed model of a `devcontainer.json` file.
+/// `https://containers.dev/implementors/json_reference/`
+///
+/// Only the properties vscli works with are typed, all other properties are kept in `other`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DevContainerConfig {
+    /// A display name for the dev container.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// The image used to create the container.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+    /// The Dockerfile build used to create the container.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub build: Option<BuildConfig>,
+    /// The Docker Compose file(s), relative to the config.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub docker_compose_file: Option<OneOrMany>,
+    /// The Docker Compose service vscode connects to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub service: Option<String>,
+    /// The Docker Compose services that are started.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub run_services: Option<Vec<String>>,
+    /// The path vscode opens inside the container.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workspace_folder: Option<String>,
+    /// The mount of the local workspace, in `docker run --mount` syntax.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workspace_mount: Option<String>,
+    /// Dev container features and their options.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub features: BTreeMap<String, serde_json::Value>,
+    /// Ports that are forwarded from the container.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub forward_ports: Vec<ForwardPort>,
+    /// Additional mounts of the container.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub mounts: Vec<Mount>,
+    /// Tool specific settings, keyed by tool name (e.g. `vscode`).
+    #[serde(default, skip_serializing_if = "serde_json::Map::is_empty")]
+    pub customizations: serde_json::Map<String, serde_json::Value>,
+    /// Runs on the host before the container is created.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub initialize_command: Option<LifecycleCommand>,
+    /// Runs once when the container is created.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_create_command: Option<LifecycleCommand>,
+    /// Runs when new content is available in the source tree.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub update_content_command: Option<LifecycleCommand>,
+    /// Runs after the container was created.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub post_create_command: Option<LifecycleCommand>,
+    /// Runs every time the container is started.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub post_start_command: Option<LifecycleCommand>,
+    /// Runs every time a tool attaches to the container.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub post_attach_command: Option<LifecycleCommand>,
+    /// All other properties, kept as they are.
+    #[serde(flatten)]
+    pub other: serde_json::Map<String, serde_json::Value>,
+}
+
+/// The `build` property of a dev container config.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BuildConfig {
+    /// The Dockerfile, relative to the config.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dockerfile: Option<String>,
+    /// The build context, relative to the config.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context: Option<String>,
+    /// All other properties (e.g. `args` or `target`), kept as they are.
+    #[serde(flatten)]
+    pub other: serde_json::Map<String, serde_json::Value>,
+}
+
+/// The vscli specific settings in `customizations.vscli`, shared with everyone using the repository.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VscliCustomizations {
+    /// The preferred editor, which has to be the id of a known editor.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub editor: Option<String>,
+    /// The folder to open, relative to the workspace.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subfolder: Option<String>,
+    /// Additional arguments passed to the editor, limited to the ones that only change how the window is opened.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+    /// The name displayed in the pickers, instead of `name`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+}
+
+/// A property that accepts either a single string or a list of strings.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum OneOrMany {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl OneOrMany {
+    /// Returns all values as a list.
+    pub fn to_vec(&self) -> Vec<String> {
+        match self {
+            Self::One(value) => vec![value.clone()],
+            Self::Many(values) => values.clone(),
+        }
+    }
+}
+
+/// An entry of `forwardPorts`, either a port number or a `host:port` string.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ForwardPort {
+    Port(u16),
+    Address(String),
+}
+
+/// An entry of `mounts`, either in `docker run --mount` syntax or as an object.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Mount {
+    String(String),
+    Object(serde_json::Map<String, serde_json::Value>),
+}
+
+/// A lifecycle command: a shell command, an exec style argument list or named parallel commands.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum LifecycleCommand {
+    Shell(String),
+    Exec(Vec<String>),
+    Parallel(BTreeMap<String, LifecycleCommand>),
+}
+
+impl DevContainerConfig {
+    /// Reads and parses a dev container config file.
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path).wrap_err_with(|| {
+            format!(
+                "Failed to read dev container config file: {}",
+                path.display()
+            )
+        })?;
+        Self::parse(path, &content)
+    }
+
+    /// Parses the content of a dev container config file (JSON with comments and trailing commas).
+    pub fn parse(path: &Path, content: &str) -> Result<Self> {
+        let path_log = path.display();
+
+        let config: Self = json5::from_str(content)
+            .wrap_err_with(|| format!("Failed to parse dev container config: {path_log}"))?;
+
+        debug!("Parsed dev container config: {path_log}");
+        Ok(config)
+    }
+
+    /// Returns the vscli settings from `customizations.vscli`.
+    ///
+    /// Invalid customizations are ignored with a warning, so they don't prevent opening the container.
+    pub fn vscli_customizations(&self) -> VscliCustomizations {
+        let Some(value) = self.customizations.get("vscli") else {
+            return VscliCustomizations::default();
+        };
+        serde_json::from_value(value.clone()).unwrap_or_else(|err| {
+            warn!("Ignoring invalid `customizations.vscli`: {err}");
+            VscliCustomizations::default()
+        })
+    }
+
+    /// Returns the config with the variables substituted in every string property.
+    pub fn substitute(&self, variables: &Variables) -> Result<Self> {
+        let mut value = serde_json::to_value(self)?;
+        variables.substitute_json(&mut value);
+        serde_json::from_value(value).wrap_err("Invalid dev container config after substitution")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_parse_typed_config() {
+        let config = DevContainerConfig::parse(
+            &PathBuf::from("devcontainer.json"),
+            r#"{
+                "name": "Full",
+                "build": { "dockerfile": "Dockerfile", "args": { "VARIANT": "1" } },
+                "forwardPorts": [3000, "db:5432"],
+                "mounts": ["source=cache,target=/cache,type=volume", { "type": "bind", "source": "/tmp", "target": "/tmp" }],
+                "postCreateCommand": { "deps": "npm install", "build": ["make", "all"] },
+                "remoteUser": "vscode",
+                "customizations": {
+                    "vscode": { "extensions": ["rust-lang.rust-analyzer"] },
+                    "vscli": { "editor": "cursor", "subfolder": "app", "args": ["--new-window"], "displayName": "Full (team)" },
+                },
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.name.as_deref(), Some("Full"));
+        assert_eq!(
+            config.build.as_ref().unwrap().dockerfile.as_deref(),
+            Some("Dockerfile")
+        );
+        assert!(config.build.as_ref().unwrap().other.contains_key("args"));
+        assert_eq!(
+            config.forward_ports,
+            vec![
+                ForwardPort::Port(3000),
+                ForwardPort::Address("db:5432".to_string())
+            ]
+        );
+        assert_eq!(config.mounts.len(), 2);
+        assert!(matches!(
+            config.post_create_command,
+            Some(LifecycleCommand::Parallel(_))
+        ));
+        // unknown properties are kept
+        assert_eq!(config.other["remoteUser"], "vscode");
+
+        let customizations = config.vscli_customizations();
+        assert_eq!(customizations.editor.as_deref(), Some("cursor"));
+        assert_eq!(customizations.subfolder.as_deref(), Some("app"));
+        assert_eq!(customizations.args, vec!["--new-window".to_string()]);
+        assert_eq!(customizations.display_name.as_deref(), Some("Full (team)"));
+    }
+}
@@ -0,0 +1,81 @@
+use std::path::{Path, PathBuf};
+
+use super::is_workspace_file;
+
+/// A file that is focused after opening the workspace, optionally at a specific position.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Goto {
+    /// The canonicalized host path of the file.
+    pub file: PathBuf,
+    /// The line to jump to (1-based).
+    pub line: Option<u32>,
+    /// The column to jump to (1-based).
+    pub column: Option<u32>,
+}
+
+impl Goto {
+    /// Parses a `path[:line[:column]]` argument.
+    ///
+    /// Returns `None` if the path does not point to an existing file (or points to a workspace file).
+    pub fn parse(arg: &Path) -> Option<Self> {
+        let (path, line, column) = if arg.is_file() {
+            (arg.to_path_buf(), None, None)
+        } else {
+            let arg = arg.to_str()?;
+            let (rest, last) = arg.rsplit_once(':')?;
+            let last: u32 = last.parse().ok()?;
+            match rest.rsplit_once(':') {
+                Some((path, line)) if line.parse::<u32>().is_ok() => {
+                    (PathBuf::from(path), line.parse().ok(), Some(last))
+                }
+                _ => (PathBuf::from(rest), Some(last), None),
+            }
+        };
+
+        if !path.is_file() || is_workspace_file(&path) {
+            return None;
+        }
+
+        Some(Self {
+            file: std::fs::canonicalize(&path).ok()?,
+            line,
+            column,
+        })
+    }
+
+    /// Returns the `:line:column` suffix understood by the editor's `--goto` flag.
+    pub fn position_suffix(&self) -> String {
+        match (self.line, self.column) {
+            (Some(line), Some(column)) => format!(":{line}:{column}"),
+            (Some(line), None) => format!(":{line}"),
+            _ => String::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_goto() {
+        let temp = tempfile::tempdir().unwrap();
+        let file = temp.path().join("main.rs");
+        std::fs::write(&file, "fn main() {}\n").unwrap();
+        let file = file.canonicalize().unwrap();
+
+        let goto = Goto::parse(&file).unwrap();
+        assert_eq!((goto.line, goto.column), (None, None));
+        assert_eq!(goto.position_suffix(), "");
+
+        let goto = Goto::parse(Path::new(&format!("{}:42", file.display()))).unwrap();
+        assert_eq!(goto.file, file);
+        assert_eq!((goto.line, goto.column), (Some(42), None));
+
+        let goto = Goto::parse(Path::new(&format!("{}:42:7", file.display()))).unwrap();
+        assert_eq!(goto.position_suffix(), ":42:7");
+
+        assert!(Goto::parse(temp.path()).is_none());
+        assert!(Goto::parse(Path::new(&format!("{}:abc", file.display()))).is_none());
+    }
+}
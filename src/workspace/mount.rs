@@ -0,0 +1,120 @@
+use color_eyre::eyre::{Result, bail};
+use std::path::{Component, Path, PathBuf};
+
+/// A parsed `workspaceMount` property, in the `--mount` syntax of `docker run`.
+/// `https://code.visualstudio.com/remote/advancedcontainers/change-default-source-mount`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct WorkspaceMount {
+    /// The mount source on the host (a path for bind mounts, a name for volumes).
+    pub source: Option<String>,
+    /// The mount target inside the container.
+    pub target: String,
+    /// The mount type (e.g. `bind` or `volume`).
+    pub kind: Option<String>,
+}
+
+impl WorkspaceMount {
+    /// Parses a mount string like `source=/path,target=/workspace,type=bind,consistency=cached`.
+    pub fn parse(mount: &str) -> Result<Self> {
+        let mut source = None;
+        let mut target = None;
+        let mut kind = None;
+
+        for option in mount.split(',').map(str::trim).filter(|o| !o.is_empty()) {
+            let (key, value) = option.split_once('=').unwrap_or((option, ""));
+            match key.trim() {
+                "source" | "src" => source = Some(value.trim().to_owned()),
+                "target" | "destination" | "dst" => target = Some(value.trim().to_owned()),
+                "type" => kind = Some(value.trim().to_owned()),
+                _ => {}
+            }
+        }
+
+        let Some(target) = target.filter(|target| !target.is_empty()) else {
+            bail!("`workspaceMount` does not specify a target: {mount}");
+        };
+
+        Ok(Self {
+            source,
+            target,
+            kind,
+        })
+    }
+
+    /// Works out where the given host folder is located inside the container.
+    ///
+    /// If the folder is inside the mounted source, the relative path is appended to the target.
+    /// Otherwise (e.g. for volumes) the target itself is used.
+    pub fn container_path(&self, host_folder: &Path) -> String {
+        let is_bind = self.kind.as_deref().is_none_or(|kind| kind == "bind");
+        let relative = self
+            .source
+            .as_deref()
+            .filter(|_| is_bind)
+            .map(|source| normalize(Path::new(source)))
+            .and_then(|source| {
+                normalize(host_folder)
+                    .strip_prefix(&source)
+                    .ok()
+                    .map(Path::to_path_buf)
+            });
+
+        let mut path = self.target.trim_end_matches('/').to_owned();
+        if let Some(relative) = relative.filter(|relative| !relative.as_os_str().is_empty()) {
+            path.push('/');
+            path.push_str(&relative.to_string_lossy().replace('\\', "/"));
+        }
+        if path.is_empty() {
+            path.push('/');
+        }
+        path
+    }
+}
+
+/// Lexically resolves `.` and `..` components, without touching the filesystem.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_workspace_mount() {
+        let mount = WorkspaceMount::parse(
+            "source=/home/me/project/..,target=/src,type=bind,consistency=cached",
+        )
+        .unwrap();
+        assert_eq!(mount.source.as_deref(), Some("/home/me/project/.."));
+        assert_eq!(mount.target, "/src");
+        assert_eq!(mount.kind.as_deref(), Some("bind"));
+
+        assert!(WorkspaceMount::parse("source=/home/me/project,type=bind").is_err());
+    }
+
+    #[test]
+    fn test_container_path() {
+        let folder = Path::new("/home/me/project");
+
+        let parent =
+            WorkspaceMount::parse("source=/home/me/project/..,target=/src,type=bind").unwrap();
+        assert_eq!(parent.container_path(folder), "/src/project");
+
+        let same = WorkspaceMount::parse("src=/home/me/project,dst=/workspace/").unwrap();
+        assert_eq!(same.container_path(folder), "/workspace");
+
+        let volume = WorkspaceMount::parse("source=project-vol,target=/data,type=volume").unwrap();
+        assert_eq!(volume.container_path(folder), "/data");
+    }
+}
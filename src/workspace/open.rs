@@ -1,11 +1,12 @@
-use super::{DevContainer, Workspace, exec_code};
-use crate::uri::{DevcontainerUriJson, FileUriJson};
+use super::{DevContainer, Workspace, WorkspaceKind, exec_code};
+use crate::editor::{Editor, LaunchMode};
+use crate::uri::{AttachedContainerUriJson, DevcontainerUriJson, FileUriJson};
 #[cfg(unix)]
 use color_eyre::eyre::eyre;
 use color_eyre::eyre::{Result, WrapErr, bail};
 #[cfg(unix)]
 use log::debug;
-use log::trace;
+use log::{trace, warn};
 use std::ffi::OsString;
 use std::path::Path;
 #[cfg(unix)]
@@ -16,78 +17,191 @@ impl Workspace {
         &self,
         mut args: Vec<OsString>,
         dry_run: bool,
+        mode: LaunchMode,
         dev_container: &DevContainer,
-        command: &str,
+        editor: &Editor,
         subfolder: Option<&Path>,
     ) -> Result<()> {
-        if args.iter().any(|arg| arg == "--folder-uri") {
-            bail!("Specifying `--folder-uri` is not possible while using vscli.");
+        if !editor.dev_containers {
+            bail!("{} does not support opening dev containers.", editor.name);
         }
-
-        let container_folder = container_folder(dev_container, subfolder);
-        let mut workspace_path = self.path.to_string_lossy().into_owned();
-        let mut config_path = dev_container.config_path.to_string_lossy().into_owned();
-
-        #[cfg(unix)]
-        if is_wsl()? {
-            debug!("WSL detected");
-            workspace_path = wslpath2::convert(
-                workspace_path.as_str(),
-                None,
-                wslpath2::Conversion::WslToWindows,
-                true,
-            )
-            .map_err(|error| {
-                eyre!("Error while getting wslpath: {error} (path: {workspace_path:?})")
-            })?;
-            config_path = wslpath2::convert(
-                config_path.as_str(),
-                None,
-                wslpath2::Conversion::WslToWindows,
-                true,
-            )
-            .map_err(|error| {
-                eyre!("Error while getting wslpath: {error} (path: {config_path:?})")
-            })?;
+        if let Some(arg) = args
+            .iter()
+            .find(|arg| **arg == *editor.folder_uri_flag || **arg == *editor.file_uri_flag)
+        {
+            bail!(
+                "Specifying `{}` is not possible while using vscli.",
+                arg.to_string_lossy()
+            );
         }
 
-        #[cfg(windows)]
+        // only the opened dev container has to be complete, other configs of the workspace may be broken
+        if self.host.is_none()
+            && let Some(compose) = &dev_container.compose
         {
-            workspace_path = workspace_path.replace("\\\\?\\", "");
-            config_path = config_path.replace("\\\\?\\", "");
+            compose.validate()?;
         }
 
-        let folder_uri = DevcontainerUriJson {
-            host_path: workspace_path,
-            config_file: FileUriJson::new(config_path.as_str()),
+        let mut container_path = dev_container.container_folder(subfolder);
+        let uri_flag = match self.kind {
+            WorkspaceKind::Folder => &editor.folder_uri_flag,
+            WorkspaceKind::File => {
+                // the workspace file lives in the mounted folder, so it's opened by its path in the container
+                if let Some(file_name) = self.path.file_name() {
+                    if !container_path.ends_with('/') {
+                        container_path.push('/');
+                    }
+                    container_path.push_str(&file_name.to_string_lossy());
+                }
+                &editor.file_uri_flag
+            }
+        };
+        let workspace_path = self.folder().to_string_lossy().into_owned();
+        let config_path = dev_container.config_path.to_string_lossy().into_owned();
+
+        // remote workspaces are addressed through a nested ssh authority
+        let (folder_uri, remote_authority) = if let Some(host) = &self.host {
+            let folder_uri = DevcontainerUriJson {
+                config_file: FileUriJson::ssh_remote(host, &config_path),
+                host_path: workspace_path,
+            };
+            (folder_uri, format!("@ssh-remote+{host}"))
+        } else {
+            let (workspace_path, config_path) = host_paths(workspace_path, config_path)?;
+            let folder_uri = DevcontainerUriJson {
+                host_path: workspace_path,
+                config_file: FileUriJson::new(config_path.as_str()),
+            };
+            (folder_uri, String::new())
         };
         let json = serde_json::to_string(&folder_uri)?;
         trace!("Folder uri JSON: {json}");
 
-        let uri = format!(
-            "vscode-remote://dev-container+{}{container_folder}",
+        let authority = format!(
+            "vscode-remote://dev-container+{}{remote_authority}",
             hex::encode(json.as_bytes())
         );
-        args.push(OsString::from("--folder-uri"));
-        args.push(OsString::from(uri));
+        args.push(OsString::from(uri_flag));
+        args.push(OsString::from(format!("{authority}{container_path}")));
+
+        // focus a file by translating its host path into the container
+        if let Some(goto) = &self.goto {
+            if let Ok(relative) = goto.file.strip_prefix(self.folder()) {
+                let mut file_path = dev_container.workspace_path_in_container.clone();
+                for component in relative.components() {
+                    if !file_path.ends_with('/') {
+                        file_path.push('/');
+                    }
+                    file_path.push_str(&component.as_os_str().to_string_lossy());
+                }
+                args.push(OsString::from("--goto"));
+                args.push(OsString::from(&editor.file_uri_flag));
+                args.push(OsString::from(format!(
+                    "{authority}{file_path}{}",
+                    goto.position_suffix()
+                )));
+            } else {
+                warn!(
+                    "File {} is not inside the workspace, so it can't be opened in the dev container",
+                    goto.file.display()
+                );
+            }
+        }
+
+        exec_code(args, dry_run, mode, editor)
+            .wrap_err_with(|| format!("Error opening {} using dev container...", editor.name))
+    }
+}
+
+/// Opens vscode attached to an already running container.
+pub fn open_attached(
+    container: &str,
+    folder: &str,
+    mut args: Vec<OsString>,
+    dry_run: bool,
+    mode: LaunchMode,
+    editor: &Editor,
+) -> Result<()> {
+    if !editor.dev_containers {
+        bail!("{} does not support attaching to containers.", editor.name);
+    }
+    if args.iter().any(|arg| *arg == *editor.folder_uri_flag) {
+        bail!(
+            "Specifying `{}` is not possible while using vscli.",
+            editor.folder_uri_flag
+        );
+    }
+
+    let json = serde_json::to_string(&AttachedContainerUriJson::new(container))?;
+    trace!("Attached container uri JSON: {json}");
+
+    let folder = if folder.starts_with('/') {
+        folder.to_owned()
+    } else {
+        format!("/{folder}")
+    };
+    let uri = format!(
+        "vscode-remote://attached-container+{}{folder}",
+        hex::encode(json.as_bytes())
+    );
+    args.push(OsString::from(&editor.folder_uri_flag));
+    args.push(OsString::from(uri));
 
-        exec_code(args, dry_run, command)
-            .wrap_err_with(|| "Error opening vscode using dev container...")
+    exec_code(args, dry_run, mode, editor).wrap_err_with(|| {
+        format!(
+            "Error attaching {} to container `{container}`...",
+            editor.name
+        )
+    })
+}
+
+/// Converts the local workspace and config paths to the form the editor expects on the host system.
+fn host_paths(mut workspace_path: String, mut config_path: String) -> Result<(String, String)> {
+    #[cfg(unix)]
+    if is_wsl()? {
+        debug!("WSL detected");
+        workspace_path = wslpath2::convert(
+            workspace_path.as_str(),
+            None,
+            wslpath2::Conversion::WslToWindows,
+            true,
+        )
+        .map_err(|error| {
+            eyre!("Error while getting wslpath: {error} (path: {workspace_path:?})")
+        })?;
+        config_path = wslpath2::convert(
+            config_path.as_str(),
+            None,
+            wslpath2::Conversion::WslToWindows,
+            true,
+        )
+        .map_err(|error| eyre!("Error while getting wslpath: {error} (path: {config_path:?})"))?;
     }
+
+    #[cfg(windows)]
+    {
+        workspace_path = workspace_path.replace("\\\\?\\", "");
+        config_path = config_path.replace("\\\\?\\", "");
+    }
+
+    Ok((workspace_path, config_path))
 }
 
-fn container_folder(dev_container: &DevContainer, subfolder: Option<&Path>) -> String {
-    let mut folder = dev_container.workspace_path_in_container.clone();
-    if let Some(subfolder) = subfolder {
-        let subfolder = subfolder.to_string_lossy().replace('\\', "/");
-        if !subfolder.is_empty() && subfolder != "." {
-            if !folder.ends_with('/') {
-                folder.push('/');
+impl DevContainer {
+    /// Returns the folder that is opened inside the container, for a subfolder of the workspace.
+    pub fn container_folder(&self, subfolder: Option<&Path>) -> String {
+        let mut folder = self.workspace_path_in_container.clone();
+        if let Some(subfolder) = subfolder {
+            let subfolder = subfolder.to_string_lossy().replace('\\', "/");
+            if !subfolder.is_empty() && subfolder != "." {
+                if !folder.ends_with('/') {
+                    folder.push('/');
+                }
+                folder.push_str(&subfolder);
             }
-            folder.push_str(&subfolder);
         }
+        folder
     }
-    folder
 }
 
 #[cfg(unix)]
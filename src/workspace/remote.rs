@@ -0,0 +1,148 @@
+use super::{DevContainer, DevContainerConfig, Workspace, WorkspaceKind};
+use color_eyre::eyre::{Result, WrapErr, bail, eyre};
+use log::{debug, trace};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// The prefix of workspace paths that live on a remote ssh host.
+pub const SSH_URI_PREFIX: &str = "ssh://";
+
+/// Checks whether the given workspace argument is an `ssh://` URI.
+pub fn is_ssh_uri(path: &Path) -> bool {
+    path.to_str()
+        .is_some_and(|path| path.starts_with(SSH_URI_PREFIX))
+}
+
+impl Workspace {
+    /// Creates a new `Workspace` from an `ssh://[user@]host/path` URI.
+    pub fn from_ssh_uri(uri: &str) -> Result<Workspace> {
+        let rest = uri
+            .strip_prefix(SSH_URI_PREFIX)
+            .ok_or_else(|| eyre!("Not an ssh URI: {uri}"))?;
+        let (host, path) = rest
+            .split_once('/')
+            .ok_or_else(|| eyre!("Missing remote path in ssh URI: {uri}"))?;
+
+        if host.is_empty() {
+            bail!("Missing host in ssh URI: {uri}");
+        }
+        if host
+            .rsplit_once(':')
+            .is_some_and(|(_, port)| !port.is_empty())
+        {
+            bail!(
+                "Ports are not supported in ssh URIs, use a host alias from your ssh config instead: {uri}"
+            );
+        }
+
+        Self::from_remote(host, Path::new(&format!("/{path}")))
+    }
+
+    /// Creates a new `Workspace` from a path on a remote ssh host.
+    pub fn from_remote(host: &str, path: &Path) -> Result<Workspace> {
+        let workspace_name = path
+            .file_name()
+            .ok_or_else(|| eyre!("Error getting workspace from remote path"))?
+            .to_string_lossy()
+            .into_owned();
+
+        let ws = Workspace {
+            path: path.to_owned(),
+            name: workspace_name,
+            kind: WorkspaceKind::Folder,
+            host: Some(host.to_owned()),
+            goto: None,
+        };
+        trace!("{ws:?}");
+        Ok(ws)
+    }
+
+    /// Finds all dev container configs of a remote workspace, using the same locations as locally.
+    pub(super) fn find_remote_dev_container_configs(&self, host: &str) -> Vec<PathBuf> {
+        let folder = shell_quote(&self.path.to_string_lossy());
+        let script = format!(
+            "ls -1d {folder}/.devcontainer.json {folder}/.devcontainer/devcontainer.json {folder}/.devcontainer/*/devcontainer.json 2>/dev/null"
+        );
+
+        // `ls` exits with an error if one of the locations does not exist, so only stdout is used
+        let configs: Vec<PathBuf> = match ssh(host, &script) {
+            Ok(output) => String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .filter(|line| !line.is_empty())
+                .map(PathBuf::from)
+                .collect(),
+            Err(err) => {
+                debug!("Could not list remote dev container configs: {err}");
+                Vec::new()
+            }
+        };
+
+        debug!(
+            "Found {} remote dev container configs on {host}: {configs:?}",
+            configs.len()
+        );
+        configs
+    }
+}
+
+impl DevContainer {
+    /// Creates a new `DevContainer` from a dev container config file on a remote ssh host.
+    pub fn from_remote_config(
+        host: &str,
+        path: &Path,
+        workspace_folder: &Path,
+    ) -> Result<DevContainer> {
+        let path_log = path.display();
+        let output = ssh(
+            host,
+            &format!("cat {}", shell_quote(&path.to_string_lossy())),
+        )?;
+        if !output.status.success() {
+            bail!(
+                "Failed to read remote dev container config file: {host}:{path_log} ({})",
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+
+        let content = String::from_utf8(output.stdout)
+            .wrap_err_with(|| format!("Remote config file is not valid UTF-8: {path_log}"))?;
+        let config = DevContainerConfig::parse(path, &content)?;
+        Self::from_parsed_config(path, &config, workspace_folder)
+    }
+}
+
+/// Runs a shell command on the remote host.
+fn ssh(host: &str, script: &str) -> Result<std::process::Output> {
+    trace!("ssh {host}: {script}");
+    Command::new("ssh")
+        .arg(host)
+        .arg(script)
+        .output()
+        .wrap_err_with(|| format!("Failed to run `ssh {host}`"))
+}
+
+/// Quotes a string so it's passed as a single argument to a posix shell.
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_ssh_uri() {
+        let workspace = Workspace::from_ssh_uri("ssh://me@buildbox/home/me/project").unwrap();
+        assert_eq!(workspace.host.as_deref(), Some("me@buildbox"));
+        assert_eq!(workspace.path, PathBuf::from("/home/me/project"));
+        assert_eq!(workspace.name, "project");
+
+        assert!(Workspace::from_ssh_uri("ssh://buildbox").is_err());
+        assert!(Workspace::from_ssh_uri("ssh://buildbox:22/project").is_err());
+    }
+
+    #[test]
+    fn test_shell_quote() {
+        assert_eq!(shell_quote("/it's here"), r"'/it'\''s here'");
+    }
+}
@@ -0,0 +1,254 @@
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Holds the values used to substitute the variables of a dev container config.
+/// `https://containers.dev/implementors/json_reference/#variables-in-devcontainerjson`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Variables {
+    /// The host path of the workspace folder.
+    local_workspace_folder: String,
+    /// The path of the workspace folder inside the container, once it is known.
+    container_workspace_folder: Option<String>,
+    /// The id of the dev container, derived from the host workspace and config paths.
+    devcontainer_id: String,
+}
+
+impl Variables {
+    /// Creates the variables for the given host workspace folder and config file.
+    pub fn new(local_workspace_folder: &Path, config_path: &Path) -> Self {
+        let local_workspace_folder = local_workspace_folder.to_string_lossy().into_owned();
+        let devcontainer_id =
+            devcontainer_id(&local_workspace_folder, &config_path.to_string_lossy());
+
+        Self {
+            local_workspace_folder,
+            container_workspace_folder: None,
+            devcontainer_id,
+        }
+    }
+
+    /// Sets the workspace folder inside the container, which enables the `containerWorkspaceFolder*` variables.
+    pub fn set_container_workspace_folder(&mut self, folder: &str) {
+        self.container_workspace_folder = Some(folder.to_owned());
+    }
+
+    /// The default workspace folder inside the container (`/workspaces/<basename>`).
+    pub fn default_container_workspace_folder(&self) -> String {
+        format!("/workspaces/{}", basename(&self.local_workspace_folder))
+    }
+
+    /// Substitutes all variables in the given string.
+    ///
+    /// Supports the following variables:
+    /// - `${localEnv:VAR}` and `${localEnv:VAR:default}` - An environment variable of the host
+    /// - `${localWorkspaceFolder}` - The host path of the workspace folder
+    /// - `${localWorkspaceFolderBasename}` - The name of the workspace folder
+    /// - `${containerWorkspaceFolder}` - The path of the workspace folder in the container
+    /// - `${containerWorkspaceFolderBasename}` - The name of the workspace folder in the container
+    /// - `${devcontainerId}` - A stable id for the dev container
+    ///
+    /// Unknown variables (like `${containerEnv:VAR}`, which only resolve inside the container) are kept as is.
+    pub fn substitute(&self, value: &str) -> String {
+        let mut result = String::with_capacity(value.len());
+        let mut rest = value;
+
+        while let Some(start) = rest.find("${") {
+            result.push_str(&rest[..start]);
+            let after = &rest[start + 2..];
+            let Some(end) = after.find('}') else {
+                rest = &rest[start..];
+                break;
+            };
+
+            let expression = &after[..end];
+            match self.resolve(expression) {
+                Some(resolved) => result.push_str(&resolved),
+                None => result.push_str(&rest[start..start + end + 3]),
+            }
+            rest = &after[end + 1..];
+        }
+
+        result.push_str(rest);
+        result
+    }
+
+    /// Substitutes all variables in every string of a JSON value.
+    pub fn substitute_json(&self, value: &mut serde_json::Value) {
+        match value {
+            serde_json::Value::String(string) => *string = self.substitute(string),
+            serde_json::Value::Array(values) => {
+                for value in values {
+                    self.substitute_json(value);
+                }
+            }
+            serde_json::Value::Object(map) => {
+                for value in map.values_mut() {
+                    self.substitute_json(value);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn resolve(&self, expression: &str) -> Option<String> {
+        let mut parts = expression.splitn(3, ':');
+        let name = parts.next()?;
+
+        match name {
+            "localEnv" | "env" => {
+                let variable = parts.next()?;
+                let default = parts.next();
+                std::env::var(variable)
+                    .ok()
+                    .or_else(|| default.map(ToOwned::to_owned))
+                    .or_else(|| Some(String::new()))
+            }
+            "localWorkspaceFolder" => Some(self.local_workspace_folder.clone()),
+            "localWorkspaceFolderBasename" => {
+                Some(basename(&self.local_workspace_folder).to_owned())
+            }
+            "containerWorkspaceFolder" => self.container_workspace_folder.clone(),
+            "containerWorkspaceFolderBasename" => self
+                .container_workspace_folder
+                .as_deref()
+                .map(|folder| basename(folder).to_owned()),
+            "devcontainerId" => Some(self.devcontainer_id.clone()),
+            _ => None,
+        }
+    }
+}
+
+/// Returns the last component of a path, accepting both `/` and `\` separators.
+fn basename(path: &str) -> &str {
+    path.trim_end_matches(['/', '\\'])
+        .rsplit(['/', '\\'])
+        .next()
+        .unwrap_or(path)
+}
+
+/// Computes the `devcontainerId` the same way the dev container CLI does:
+/// the sha256 hash of the sorted id labels as JSON, formatted in base 32 and padded to 52 characters.
+fn devcontainer_id(local_folder: &str, config_file: &str) -> String {
+    let labels = BTreeMap::from([
+        ("devcontainer.config_file", config_file),
+        ("devcontainer.local_folder", local_folder),
+    ]);
+    let json = serde_json::to_string(&labels).expect("Labels are serializable");
+    let mut digits: Vec<u8> = Sha256::digest(json.as_bytes()).to_vec();
+
+    // repeatedly divide the big-endian number by 32 to collect its base 32 digits
+    let mut encoded = Vec::new();
+    while digits.iter().any(|&byte| byte != 0) {
+        let mut remainder = 0u32;
+        for byte in &mut digits {
+            let value = (remainder << 8) | u32::from(*byte);
+            *byte = u8::try_from(value / 32).expect("Quotient fits into a byte");
+            remainder = value % 32;
+        }
+        encoded.push(char::from_digit(remainder, 32).expect("Remainder is a valid base 32 digit"));
+    }
+
+    let id: String = encoded.iter().rev().collect();
+    format!("{id:0>52}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn variables() -> Variables {
+        Variables::new(
+            Path::new("/home/me/my-project"),
+            Path::new("/home/me/my-project/.devcontainer/devcontainer.json"),
+        )
+    }
+
+    #[test]
+    fn test_substitute_local_variables() {
+        let variables = variables();
+
+        assert_eq!(
+            variables.substitute("/workspaces/${localWorkspaceFolderBasename}"),
+            "/workspaces/my-project"
+        );
+        assert_eq!(
+            variables.substitute("source=${localWorkspaceFolder}/..,target=/src"),
+            "source=/home/me/my-project/..,target=/src"
+        );
+        assert_eq!(variables.substitute("/custom/path"), "/custom/path");
+    }
+
+    #[test]
+    fn test_substitute_local_env() {
+        let variables = variables();
+
+        assert_eq!(
+            variables.substitute("${localEnv:VSCLI_TEST_SURELY_UNSET:fallback}"),
+            "fallback"
+        );
+        assert_eq!(
+            variables.substitute("${localEnv:VSCLI_TEST_SURELY_UNSET}"),
+            ""
+        );
+        assert_eq!(
+            variables.substitute("${localEnv:PATH}"),
+            std::env::var("PATH").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_substitute_container_variables() {
+        let mut variables = variables();
+
+        // unknown until the container folder is set
+        assert_eq!(
+            variables.substitute("${containerWorkspaceFolder}/src"),
+            "${containerWorkspaceFolder}/src"
+        );
+
+        variables.set_container_workspace_folder("/src/my-project");
+        assert_eq!(
+            variables.substitute("${containerWorkspaceFolder}/src"),
+            "/src/my-project/src"
+        );
+        assert_eq!(
+            variables.substitute("${containerWorkspaceFolderBasename}"),
+            "my-project"
+        );
+        assert_eq!(
+            variables.substitute("${containerEnv:HOME} ${unclosed"),
+            "${containerEnv:HOME} ${unclosed"
+        );
+    }
+
+    #[test]
+    fn test_devcontainer_id() {
+        let id = variables().substitute("${devcontainerId}");
+        assert_eq!(id.len(), 52);
+        assert!(
+            id.chars()
+                .all(|c| c.is_ascii_digit() || c.is_ascii_lowercase())
+        );
+        assert_eq!(id, variables().substitute("${devcontainerId}"));
+    }
+
+    #[test]
+    fn test_devcontainer_id_matches_cli() {
+        // computed with `devcontainerIdForLabels` of the dev container CLI
+        assert_eq!(
+            devcontainer_id(
+                "/home/me/my-project",
+                "/home/me/my-project/.devcontainer/devcontainer.json"
+            ),
+            "0ms8qns6e560r4blhdk1fpbcgpjg7jafku3hqhqveh707tfc5o0h"
+        );
+        assert_eq!(
+            devcontainer_id(
+                r"C:\Users\jürgen\app",
+                r"C:\Users\jürgen\app\.devcontainer.json"
+            ),
+            "1aff2u5eup5tfgn0n4o2rmvj6arh19d63ga9jvrjc7236rfnk4is"
+        );
+    }
+}